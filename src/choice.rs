@@ -0,0 +1,399 @@
+use sdl2::mouse::SystemCursor;
+use sdl2::keyboard::Keycode;
+use crate::primitives::{DrawCtx, Point, Rect, Radii, Border, Paint, InBounds, lighten};
+use crate::render_text::{TextParams};
+use crate::stroke::{StrokeStyle, LineCap, LineJoin};
+use crate::interface::{EventCtx};
+use crate::widgets::{Widget, WidgetResponse, WidgetStatus, Message, just_status, MDDoc};
+use crate::events::{MouseEvent};
+
+//gap between an indicator and its text, relative to the line height
+const TEXT_GAP: f32 = 0.3;
+
+fn indicator_side(ctx: &DrawCtx) -> f32 {
+    ctx.render_text.line_height(1.) * 0.7
+}
+
+//outline of an indicator, thicker and in the focus color while it has keyboard focus
+fn indicator_border(focused: bool, ctx: &DrawCtx) -> Border {
+    let theme = &ctx.theme;
+    if focused {
+        Border::new(Point::new(theme.focus_width, theme.focus_width), theme.palette.focus)
+    } else {
+        Border::new(Point::new(theme.border_width, theme.border_width), theme.palette.border)
+    }
+}
+
+fn change_response() -> Option<WidgetResponse> {
+    Some(just_status(WidgetStatus::REDRAW).msg(Message::Changed))
+}
+
+//how a BoolInput draws its on/off indicator
+pub trait BoolStyle {
+    fn indicator_size(side: f32) -> Point;
+    fn draw_indicator(r: &Rect, on: bool, focused: bool, hovered: bool, ctx: &DrawCtx);
+}
+
+pub struct CheckStyle { }
+pub struct SwitchStyle { }
+
+impl BoolStyle for CheckStyle {
+    fn indicator_size(side: f32) -> Point {
+        Point::new(side, side)
+    }
+    fn draw_indicator(r: &Rect, on: bool, focused: bool, hovered: bool, ctx: &DrawCtx) {
+        let p = &ctx.theme.palette;
+        let fill = match (on, hovered) {
+            (true, _) => p.accent,
+            (false, true) => p.hover,
+            (false, false) => p.widget_bg
+        };
+        ctx.draw_round_rect(r.clone(), Radii::all(ctx.theme.radius), Paint::Solid(fill), Some(&indicator_border(focused, ctx)));
+        if on {
+            let (w, h) = (r.width(), r.height());
+            let pts: Vec<Point> = [(0.22, 0.52), (0.42, 0.72), (0.78, 0.3)].iter()
+                .map(|(x, y)| r.c1 + Point::new(w * x, h * y))
+                .collect();
+            let style = StrokeStyle::new(w * 0.14).cap(LineCap::Round).join(LineJoin::Round);
+            ctx.draw_polyline(&pts, false, p.accent_text, &style);
+        }
+    }
+}
+
+impl BoolStyle for SwitchStyle {
+    fn indicator_size(side: f32) -> Point {
+        Point::new(side * 1.8, side)
+    }
+    fn draw_indicator(r: &Rect, on: bool, focused: bool, hovered: bool, ctx: &DrawCtx) {
+        let p = &ctx.theme.palette;
+        let h = r.height();
+        let track = if on { p.accent } else { p.border };
+        let track = if hovered { lighten(&track, 0.2) } else { track };
+        let border = if focused { Some(indicator_border(true, ctx)) } else { None };
+        ctx.draw_round_rect(r.clone(), Radii::all(h / 2.), Paint::Solid(track), border.as_ref());
+        let inset = h * 0.15;
+        let d = h - inset * 2.;
+        let x = if on { r.c2.x - inset - d } else { r.c1.x + inset };
+        let knob = Rect::new(Point::new(x, r.c1.y + inset), Point::new(x + d, r.c1.y + inset + d));
+        ctx.draw_round_rect(knob, Radii::all(d / 2.), Paint::Solid(p.widget_bg), None);
+    }
+}
+
+//a yes/no value with optional text next to it. Clicking anywhere on it or pressing
+//space or return while it has focus flips the value
+pub struct BoolInput<S: BoolStyle> {
+    checked: bool,
+    text: String,
+    values: (String, String),
+    focused: bool,
+    hovered: bool,
+    size: Point,
+    style: std::marker::PhantomData<S>
+}
+
+pub type Checkbox = BoolInput<CheckStyle>;
+pub type Toggle = BoolInput<SwitchStyle>;
+
+#[allow(dead_code)]
+impl<S: BoolStyle> BoolInput<S> {
+    pub fn new<T: Into<String>>(text: T, checked: bool, ctx: &DrawCtx) -> Self {
+        let mut w = BoolInput { checked, text: text.into(), values: (String::from("Yes"), String::from("No")),
+            focused: false, hovered: false, size: Point::origin(), style: std::marker::PhantomData };
+        w.remeasure(ctx);
+        w
+    }
+    //what gets written to the markdown when checked and unchecked
+    pub fn values<T: Into<String>>(mut self, on: T, off: T) -> Self {
+        self.values = (on.into(), off.into());
+        self
+    }
+    pub fn checked(&self) -> bool {
+        self.checked
+    }
+    pub fn set_checked(&mut self, checked: bool) {
+        self.checked = checked;
+    }
+    fn indicator_rect(&self, ctx: &DrawCtx) -> Rect {
+        let size = S::indicator_size(indicator_side(ctx));
+        let c1 = Point::new(0., (self.size.y - size.y) / 2.);
+        Rect::new(c1, c1 + size)
+    }
+    fn flip(&mut self) -> Option<WidgetResponse> {
+        self.checked = !self.checked;
+        change_response()
+    }
+    fn key(&mut self, kc: &Keycode) -> Option<WidgetResponse> {
+        if !self.focused {
+            return None
+        }
+        match *kc {
+            Keycode::Space | Keycode::Return => self.flip(),
+            _ => None
+        }
+    }
+}
+
+pub fn new_checkbox(checked: bool, ctx: &DrawCtx) -> Box<dyn Widget> {
+    Box::new(Checkbox::new("", checked, ctx))
+}
+
+pub fn new_toggle(checked: bool, ctx: &DrawCtx) -> Box<dyn Widget> {
+    Box::new(Toggle::new("", checked, ctx))
+}
+
+impl<S: BoolStyle> Widget for BoolInput<S> {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        self.size
+    }
+    fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
+        let lh = ctx.render_text.line_height(1.);
+        let indicator = S::indicator_size(indicator_side(ctx));
+        let text = ctx.render_text.measure(&self.text, 1.);
+        let gap = if self.text.is_empty() { 0. } else { lh * TEXT_GAP };
+        self.size = Point::new(indicator.x + gap + text.x, lh.max(indicator.y));
+        self.size
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        Some(ctx.render_text.baseline(1.))
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        let r = self.indicator_rect(ctx);
+        S::draw_indicator(&Rect::new(*off + r.c1, *off + r.c2), self.checked, self.focused, self.hovered, ctx);
+        if !self.text.is_empty() {
+            let x = r.width() + ctx.render_text.line_height(1.) * TEXT_GAP;
+            ctx.draw_text(&self.text, Rect::new(*off + Point::new(x, 0.), *off + self.size), &TextParams::new());
+        }
+    }
+    //the press takes focus, the value changes on the click after the release
+    fn mouse_event(&mut self, ev: &MouseEvent, _: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        match *ev {
+            MouseEvent::Move => {
+                *ctx.cursor = SystemCursor::Hand;
+                return Some(just_status(WidgetStatus::FINE))
+            }
+            MouseEvent::Enter => self.hovered = true,
+            MouseEvent::Leave => self.hovered = false,
+            MouseEvent::Press { .. } => self.focused = true,
+            MouseEvent::Click { .. } => return self.flip(),
+            _ => return None
+        }
+        Some(just_status(WidgetStatus::REDRAW))
+    }
+    fn key_down(&mut self, kc: &Keycode, _: &mut EventCtx) -> Option<WidgetResponse> {
+        self.key(kc)
+    }
+    fn deselect(&mut self) -> Option<WidgetResponse> {
+        if self.focused {
+            self.focused = false;
+            Some(just_status(WidgetStatus::REDRAW))
+        } else { None }
+    }
+    fn serialize(&self, buf: &mut MDDoc) {
+        let value = if self.checked { &self.values.0 } else { &self.values.1 };
+        buf.body.extend_from_slice(value.as_bytes())
+    }
+}
+
+//one choice out of a row of options. With focus the arrow keys move the choice
+pub struct RadioGroup {
+    options: Vec<String>,
+    selected: usize,
+    focused: bool,
+    hovered: Option<usize>,
+    //rect of each option, indicator and text, relative to the group
+    rects: Vec<Rect>,
+    size: Point
+}
+
+#[allow(dead_code)]
+impl RadioGroup {
+    pub fn new<T: Into<String>>(options: Vec<T>, selected: usize, ctx: &DrawCtx) -> Self {
+        let options: Vec<String> = options.into_iter().map(|o| o.into()).collect();
+        let selected = selected.min(options.len().max(1) - 1);
+        let mut w = RadioGroup { options, selected, focused: false, hovered: None, rects: Vec::new(), size: Point::origin() };
+        w.remeasure(ctx);
+        w
+    }
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+    pub fn value(&self) -> Option<&str> {
+        self.options.get(self.selected).map(|s| s.as_str())
+    }
+    fn option_at(&self, pt: &Point) -> Option<usize> {
+        self.rects.iter().position(|r| r.in_bounds(pt, &Point::origin()))
+    }
+    fn select(&mut self, idx: usize) -> Option<WidgetResponse> {
+        if idx == self.selected || idx >= self.options.len() {
+            return Some(just_status(WidgetStatus::FINE))
+        }
+        self.selected = idx;
+        change_response()
+    }
+    //the arrow keys wrap around at either end
+    fn key(&mut self, kc: &Keycode) -> Option<WidgetResponse> {
+        if !self.focused || self.options.is_empty() {
+            return None
+        }
+        let n = self.options.len();
+        match *kc {
+            Keycode::Left | Keycode::Up => self.select((self.selected + n - 1) % n),
+            Keycode::Right | Keycode::Down => self.select((self.selected + 1) % n),
+            _ => None
+        }
+    }
+}
+
+pub fn new_radio_group<T: Into<String>>(options: Vec<T>, selected: usize, ctx: &DrawCtx) -> Box<dyn Widget> {
+    Box::new(RadioGroup::new(options, selected, ctx))
+}
+
+impl Widget for RadioGroup {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        self.size
+    }
+    fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
+        let lh = ctx.render_text.line_height(1.);
+        let side = indicator_side(ctx);
+        let mut x = 0.;
+        self.rects = self.options.iter().map(|o| {
+            let w = side + lh * TEXT_GAP + ctx.render_text.measure(o, 1.).x;
+            let r = Rect::new(Point::new(x, 0.), Point::new(x + w, lh));
+            x += w + ctx.theme.spacing.x;
+            r
+        }).collect();
+        let width = self.rects.last().map(|r| r.c2.x).unwrap_or(0.);
+        self.size = Point::new(width, lh);
+        self.size
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        Some(ctx.render_text.baseline(1.))
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        let p = &ctx.theme.palette;
+        let lh = ctx.render_text.line_height(1.);
+        let side = indicator_side(ctx);
+        for (i, (o, r)) in self.options.iter().zip(self.rects.iter()).enumerate() {
+            let c1 = *off + r.c1 + Point::new(0., (lh - side) / 2.);
+            let fill = if self.hovered == Some(i) { p.hover } else { p.widget_bg };
+            let focused = self.focused && i == self.selected;
+            ctx.draw_round_rect(Rect::new(c1, c1 + Point::new(side, side)), Radii::all(side / 2.),
+                Paint::Solid(fill), Some(&indicator_border(focused, ctx)));
+            if i == self.selected {
+                let inset = side * 0.28;
+                let dot = Rect::new(c1 + Point::new(inset, inset), c1 + Point::new(side - inset, side - inset));
+                ctx.draw_round_rect(dot, Radii::all(side / 2. - inset), Paint::Solid(p.accent), None);
+            }
+            ctx.draw_text(o, Rect::new(*off + r.c1 + Point::new(side + lh * TEXT_GAP, 0.), *off + r.c2), &TextParams::new());
+        }
+    }
+    fn mouse_event(&mut self, ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        match *ev {
+            MouseEvent::Move => {
+                let hovered = self.option_at(pt);
+                if hovered.is_some() {
+                    *ctx.cursor = SystemCursor::Hand;
+                }
+                if hovered == self.hovered {
+                    return Some(just_status(WidgetStatus::FINE))
+                }
+                self.hovered = hovered;
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            MouseEvent::Press { .. } => {
+                self.focused = true;
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            MouseEvent::Leave => {
+                self.hovered = None;
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            MouseEvent::Click { .. } => self.option_at(pt).and_then(|i| self.select(i)),
+            _ => None
+        }
+    }
+    fn key_down(&mut self, kc: &Keycode, _: &mut EventCtx) -> Option<WidgetResponse> {
+        self.key(kc)
+    }
+    fn deselect(&mut self) -> Option<WidgetResponse> {
+        if self.focused {
+            self.focused = false;
+            Some(just_status(WidgetStatus::REDRAW))
+        } else { None }
+    }
+    fn serialize(&self, buf: &mut MDDoc) {
+        buf.body.extend_from_slice(self.value().unwrap_or("").as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkbox(checked: bool) -> Checkbox {
+        BoolInput { checked, text: String::new(), values: (String::from("Yes"), String::from("No")),
+            focused: true, hovered: false, size: Point::origin(), style: std::marker::PhantomData }
+    }
+
+    fn radio(options: &[&str], selected: usize) -> RadioGroup {
+        RadioGroup { options: options.iter().map(|o| o.to_string()).collect(), selected, focused: true, hovered: None,
+            rects: Vec::new(), size: Point::origin() }
+    }
+
+    fn serialized(w: &dyn Widget) -> String {
+        let mut buf = MDDoc::empty();
+        w.serialize(&mut buf);
+        String::from_utf8(buf.body).unwrap()
+    }
+
+    fn changed(resp: Option<WidgetResponse>) -> bool {
+        matches!(resp.unwrap().msgs.as_slice(), [Message::Changed])
+    }
+
+    #[test]
+    fn bool_serializes_its_values() {
+        assert_eq!(serialized(&checkbox(true)), "Yes");
+        assert_eq!(serialized(&checkbox(false)), "No");
+        let w = checkbox(false).values("Long", "Short");
+        assert_eq!(serialized(&w), "Short");
+    }
+
+    #[test]
+    fn space_and_return_flip() {
+        let mut w = checkbox(false);
+        assert!(changed(w.key(&Keycode::Space)));
+        assert!(w.checked());
+        assert!(changed(w.key(&Keycode::Return)));
+        assert!(!w.checked());
+        assert!(w.key(&Keycode::A).is_none());
+        w.deselect();
+        assert!(w.key(&Keycode::Space).is_none());
+        assert!(!w.checked());
+    }
+
+    #[test]
+    fn arrows_wrap_around() {
+        let mut w = radio(&["A", "B", "C"], 0);
+        assert!(changed(w.key(&Keycode::Left)));
+        assert_eq!(w.value(), Some("C"));
+        w.key(&Keycode::Down);
+        assert_eq!(w.value(), Some("A"));
+        w.key(&Keycode::Right);
+        assert_eq!(w.selected(), 1);
+        w.key(&Keycode::Up);
+        assert_eq!(w.selected(), 0);
+        assert_eq!(serialized(&w), "A");
+    }
+
+    #[test]
+    fn select_ignores_bad_indices() {
+        let mut w = radio(&["A", "B"], 1);
+        assert!(!changed(w.select(1)));
+        assert!(!changed(w.select(2)));
+        assert_eq!(w.selected(), 1);
+        assert!(changed(w.select(0)));
+        let mut empty = radio(&[], 0);
+        assert!(empty.key(&Keycode::Right).is_none());
+        assert_eq!(serialized(&empty), "");
+    }
+}
@@ -0,0 +1,191 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use sdl2::keyboard::Keycode;
+use crate::primitives::{DrawCtx, Point, Rect, Radii, Border, Paint, InBounds};
+use crate::render_text::{TextParams};
+use crate::layout::{LayoutParams, Align, with_layout};
+use crate::textedit::{TextBox};
+use crate::overlay::{Popup, PopupId, new_anchor};
+use crate::tooltip::{wrap};
+use crate::interface::{EventCtx};
+use crate::events::{MouseEvent};
+use crate::widgets::{Widget, WidgetResponse, WidgetStatus, Message, WidgetList, Orientation, Button, Label,
+    WidgetIterT, just_status, combine_response, new_label, new_h_list, MDDoc};
+
+//message text wraps at this many chars
+const DIALOG_CHARS: usize = 50;
+
+//which button closed a dialog, prompts answer Text with what was typed instead of Ok
+#[derive(Clone, PartialEq, Debug)]
+pub enum Answer {
+    Ok,
+    Cancel,
+    Yes,
+    No,
+    Text(String)
+}
+
+//OK on a prompt answers with the text that was typed
+fn with_input(answer: Answer, input: Option<String>) -> Answer {
+    match (answer, input) {
+        (Answer::Ok, Some(text)) => Answer::Text(text),
+        (answer, _) => answer
+    }
+}
+
+//a modal box with a message, an optional text field and a row of buttons.
+//Return picks the default, the first button unless given, and Escape the last one. Closing it sends
+//Message::DialogClosed with the answer, handled by the AppState
+pub struct Dialog {
+    id: PopupId,
+    content: WidgetList,
+    //index of the text field in content for prompts
+    input: Option<usize>,
+    default: Answer,
+    cancel: Answer,
+    size: Point,
+    padding: Point
+}
+
+#[allow(dead_code)]
+impl Dialog {
+    fn new(title: &str, text: &str, input: Option<&str>, buttons: Vec<(&str, Answer)>, ctx: &DrawCtx) -> Self {
+        let spacing = ctx.theme.spacing;
+        let mut content = WidgetList::new(Orientation::Vertical, spacing.y as u32);
+        content.add(Box::new(Label::new(title, None, None, None, TextParams::new().scale(1.2))), ctx);
+        let width = ctx.render_text.measure(&"a".repeat(DIALOG_CHARS), 1.).x;
+        let lines = wrap(text, width, ctx);
+        let n_lines = lines.len();
+        for line in lines {
+            content.add(new_label(line), ctx);
+        }
+        let input = input.map(|default| {
+            let size = Point::new(width, ctx.render_text.line_height(1.));
+            content.add(Box::new(TextBox::new(default, size).focused()), ctx);
+            1 + n_lines
+        });
+        let (default, cancel) = (buttons[0].1.clone(), buttons[buttons.len() - 1].1.clone());
+        let buttons = buttons.into_iter().map(|(label, answer)| {
+            let mut b = Button::new(Message::Answer(answer)).builder(ctx);
+            b += Label::new(label, None, None, None, TextParams::new());
+            b.widget()
+        }).collect();
+        content.add(with_layout(new_h_list(buttons, spacing.x as u32, ctx),
+            LayoutParams::new().align(Align::End, Align::Start), ctx), ctx);
+        let mut d = Dialog { id: PopupId::new(), content, input, default, cancel, size: Point::origin(), padding: Point::origin() };
+        d.remeasure(ctx);
+        d
+    }
+    pub fn message(title: &str, text: &str, ctx: &DrawCtx) -> Self {
+        Dialog::new(title, text, None, vec![("OK", Answer::Ok)], ctx)
+    }
+    //default is what Return answers, No for anything that can't be undone
+    pub fn confirm(title: &str, text: &str, default: Answer, ctx: &DrawCtx) -> Self {
+        let mut d = Dialog::new(title, text, None, vec![("Yes", Answer::Yes), ("No", Answer::No)], ctx);
+        d.default = default;
+        d
+    }
+    pub fn prompt(title: &str, text: &str, default: &str, ctx: &DrawCtx) -> Self {
+        Dialog::new(title, text, Some(default), vec![("OK", Answer::Ok), ("Cancel", Answer::Cancel)], ctx)
+    }
+    pub fn id(&self) -> PopupId {
+        self.id
+    }
+    //the overlay centers modal popups in the window
+    pub fn popup(self) -> Popup {
+        let id = self.id;
+        let widget: Rc<RefCell<dyn Widget>> = Rc::new(RefCell::new(self));
+        Popup::new(id, widget, new_anchor(), Point::origin()).modal()
+    }
+    fn input_text(&self) -> Option<String> {
+        let w = self.content.get_widget(self.input?)?;
+        let mut tmp = MDDoc::empty();
+        w.serialize(&mut tmp);
+        Some(String::from_utf8_lossy(&tmp.body).into_owned())
+    }
+    fn answer(&self, answer: Answer) -> WidgetResponse {
+        let answer = with_input(answer, self.input_text());
+        WidgetResponse::new(WidgetStatus::REDRAW)
+            .msg(Message::ClosePopup(self.id))
+            .msg(Message::DialogClosed(self.id, answer))
+    }
+}
+
+impl Widget for Dialog {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        self.size
+    }
+    fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
+        self.padding = ctx.theme.padding * 2.;
+        self.size = self.content.remeasure(ctx) + self.padding * 2.;
+        self.size
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        let theme = &ctx.theme;
+        let radii = Radii::all(theme.radius * 2.);
+        let r = Rect::new(*off, *off + self.size);
+        let border = Border::new(Point::new(theme.border_width, theme.border_width), theme.palette.border);
+        ctx.draw_shadow(r.clone(), radii, &theme.shadow(4., 8.));
+        ctx.draw_round_rect(r, radii, Paint::Solid(theme.palette.background), Some(&border));
+        self.content.draw(&(*off + self.padding), ctx);
+    }
+    //the dialog takes every press on it so nothing behind it sees one
+    fn mouse_event(&mut self, _: &MouseEvent, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+        Some(just_status(WidgetStatus::FINE))
+    }
+    fn child_at(&self, pt: &Point, ctx: &DrawCtx) -> Option<usize> {
+        let r = Rect::new(self.padding, self.padding + self.content.measure(ctx));
+        if r.in_bounds(pt, &ctx.viewport) { Some(0) } else { None }
+    }
+    fn child_offset(&self, _: usize) -> Point {
+        self.padding
+    }
+    fn child(&self, idx: usize) -> Option<&dyn Widget> {
+        if idx == 0 { Some(&self.content) } else { None }
+    }
+    fn child_mut(&mut self, idx: usize) -> Option<&mut dyn Widget> {
+        if idx == 0 { Some(&mut self.content) } else { None }
+    }
+    //button clicks arrive as Answer messages and close the dialog
+    fn child_response(&mut self, _: usize, resp: WidgetResponse, _: &mut EventCtx) -> WidgetResponse {
+        let (mut out, mut answers) = (WidgetResponse::new(resp.status), Vec::new());
+        for msg in resp.msgs {
+            match msg {
+                Message::Answer(a) => answers.push(a),
+                m => out = out.msg(m)
+            }
+        }
+        answers.into_iter().fold(out, |r, a| combine_response(r, self.answer(a)))
+    }
+    fn key_down(&mut self, kc: &Keycode, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        match *kc {
+            Keycode::Return => Some(self.answer(self.default.clone())),
+            Keycode::Escape => Some(self.answer(self.cancel.clone())),
+            _ => {
+                let resp = self.content.key_down(kc, ctx);
+                Some(resp.unwrap_or(just_status(WidgetStatus::FINE)))
+            }
+        }
+    }
+    fn deselect(&mut self) -> Option<WidgetResponse> {
+        self.content.deselect();
+        Some(just_status(WidgetStatus::REDRAW))
+    }
+    fn do_serialize(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_on_a_prompt_answers_the_text() {
+        assert_eq!(with_input(Answer::Ok, Some(String::from("AMD"))), Answer::Text(String::from("AMD")));
+        assert_eq!(with_input(Answer::Ok, Some(String::new())), Answer::Text(String::new()));
+        assert_eq!(with_input(Answer::Cancel, Some(String::from("AMD"))), Answer::Cancel);
+        assert_eq!(with_input(Answer::Ok, None), Answer::Ok);
+        assert_eq!(with_input(Answer::Yes, None), Answer::Yes);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::primitives::{DrawCtx, Point, Rect, InBounds};
+use crate::widgets::{Widget, WidgetResponse, combine_response_opt};
+use crate::overlay::{Overlay, PopupId};
+use crate::interface::{EventCtx};
+
+//pointer events delivered to widgets by the MouseTracker
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MouseEvent {
+    //the mouse moved over the widget or one of its children
+    Enter,
+    Leave,
+    //clicks counts quick presses in a row, 2 for the second press of a double click
+    Press { clicks: u8 },
+    Release,
+    //press and release over the same widget
+    Click { clicks: u8 },
+    DoubleClick,
+    //mouse moved over the widget, sent to every widget under the mouse outermost first
+    //so the deepest one sets the cursor. Not sent while a button is held
+    Move,
+    //mouse moved while the button is held, sent to the widget that took the press even
+    //if the mouse has left it. start and delta are in window coordinates
+    Drag { start: Point, delta: Point }
+}
+
+//child indices from a layer's root widget down to a widget
+pub type WidgetPath = Vec<usize>;
+
+//each popup in the overlay has its own widget tree on top of the interface
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Layer {
+    Interface,
+    Popup(PopupId)
+}
+
+//path to the deepest widget under the point, pt is relative to root
+pub fn hit_path(root: &dyn Widget, pt: &Point, ctx: &DrawCtx) -> WidgetPath {
+    let mut path = Vec::new();
+    let (mut w, mut pt) = (root, *pt);
+    while let Some(i) = w.child_at(&pt, ctx) {
+        let child = match w.child(i) {
+            Some(c) => c,
+            None => break
+        };
+        pt = pt - w.child_offset(i);
+        path.push(i);
+        w = child;
+    }
+    path
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Phase {
+    //ancestors of the target, outermost first
+    Capture,
+    //the target, then its ancestors innermost first
+    Bubble
+}
+
+//delivers the event to the widget at the end of path and nowhere else,
+//its response passes the child_response of every ancestor on the way back
+pub fn send(w: &mut dyn Widget, path: &[usize], phase: Phase, ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+    match path.split_first() {
+        None => match phase {
+            Phase::Capture => w.capture(ev, pt, ctx),
+            Phase::Bubble => w.mouse_event(ev, pt, ctx)
+        },
+        Some((i, rest)) => {
+            let off = w.child_offset(*i);
+            let resp = w.child_mut(*i).and_then(|c| send(c, rest, phase, ev, &(*pt - off), ctx))?;
+            Some(w.child_response(*i, resp, ctx))
+        }
+    }
+}
+
+//deselects every widget in the tree except the ones on path, which keep their focus
+pub fn deselect_except(w: &mut dyn Widget, path: &[usize]) -> Option<WidgetResponse> {
+    let mut resp = None;
+    let mut i = 0;
+    while let Some(c) = w.child_mut(i) {
+        let r = match path.split_first() {
+            Some((j, rest)) if *j == i => deselect_except(c, rest),
+            _ => c.deselect()
+        };
+        resp = combine_response_opt(resp, r);
+        i += 1;
+    }
+    resp
+}
+
+//the widget trees the mouse can be over, in window coordinates
+pub struct Layers<'a> {
+    pub interface: &'a mut dyn Widget,
+    pub origin: Point,
+    pub overlay: &'a Overlay
+}
+
+impl<'a> Layers<'a> {
+    pub fn hit(&self, pt: &Point, ctx: &DrawCtx) -> Option<(Layer, WidgetPath)> {
+        if let Some(id) = self.overlay.popup_at(pt, ctx) {
+            let p = self.overlay.get(id)?;
+            let path = hit_path(&*p.widget.borrow(), &(*pt - p.position()), ctx);
+            return Some((Layer::Popup(id), path))
+        }
+        let r = Rect::new(self.origin, self.origin + self.interface.measure(ctx));
+        if self.overlay.modal().is_none() && r.in_bounds(pt, &ctx.viewport) {
+            Some((Layer::Interface, hit_path(self.interface, &(*pt - self.origin), ctx)))
+        }
+        else { None }
+    }
+    pub fn send(&mut self, layer: Layer, path: &[usize], phase: Phase, ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        match layer {
+            Layer::Interface => send(self.interface, path, phase, ev, &(*pt - self.origin), ctx),
+            Layer::Popup(id) => {
+                let p = self.overlay.get(id)?;
+                let resp = send(&mut *p.widget.borrow_mut(), path, phase, ev, &(*pt - p.position()), ctx);
+                resp
+            }
+        }
+    }
+    //moves the focus to the widget that was pressed. Popups keep the interface's focus
+    //so a field stays focused while its suggestions are clicked
+    pub fn focus(&mut self, hit: &Option<(Layer, WidgetPath)>) -> Option<WidgetResponse> {
+        match hit {
+            Some((Layer::Interface, path)) => deselect_except(self.interface, path),
+            Some((Layer::Popup(id), path)) => {
+                let p = self.overlay.get(*id)?;
+                let resp = deselect_except(&mut *p.widget.borrow_mut(), path);
+                resp
+            }
+            None if self.overlay.modal().is_none() => self.interface.deselect(),
+            None => None
+        }
+    }
+    //runs the capture phase down to the widget at the end of path and then the bubble
+    //phase back up, stopping at the first widget that responds.
+    //Returns the length of the path to that widget along with its response
+    pub fn dispatch(&mut self, layer: Layer, path: &[usize], ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<(usize, WidgetResponse)> {
+        let capture = (0..path.len()).map(|n| (n, Phase::Capture));
+        let bubble = (0..=path.len()).rev().map(|n| (n, Phase::Bubble));
+        capture.chain(bubble)
+            .filter_map(|(n, phase)| self.send(layer, &path[..n], phase, ev, pt, ctx).map(|r| (n, r)))
+            .next()
+    }
+}
+
+//turns raw button and motion events into enter/leave, click and drag events
+//for the widgets under the mouse
+pub struct MouseTracker {
+    hover: Option<(Layer, WidgetPath)>,
+    //widget that took the press and the window position of the press
+    pressed: Option<(Layer, WidgetPath, Point)>,
+    clicks: u8
+}
+
+impl MouseTracker {
+    pub fn new() -> Self {
+        MouseTracker { hover: None, pressed: None, clicks: 0 }
+    }
+    pub fn is_pressed(&self) -> bool {
+        self.pressed.is_some()
+    }
+    pub fn motion(&mut self, layers: &mut Layers, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        let mut resp = None;
+        if let Some((layer, ref path, start)) = self.pressed {
+            let drag = MouseEvent::Drag { start, delta: *pt - start };
+            resp = layers.dispatch(layer, path, &drag, pt, ctx).map(|r| r.1);
+        }
+        let hit = layers.hit(pt, ctx.draw_ctx);
+        resp = combine_response_opt(resp, self.set_hover(hit, layers, pt, ctx));
+        if let (None, Some((layer, ref path))) = (&self.pressed, &self.hover) {
+            for n in 0..=path.len() {
+                resp = combine_response_opt(resp, layers.send(*layer, &path[..n], Phase::Bubble, &MouseEvent::Move, pt, ctx));
+            }
+        }
+        resp
+    }
+    pub fn press(&mut self, layers: &mut Layers, pt: &Point, clicks: u8, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        let hit = layers.hit(pt, ctx.draw_ctx);
+        let focus = layers.focus(&hit);
+        let (layer, path) = match hit {
+            Some(h) => h,
+            None => return focus
+        };
+        self.clicks = clicks;
+        let ev = MouseEvent::Press { clicks };
+        let resp = match layers.dispatch(layer, &path, &ev, pt, ctx) {
+            Some((n, resp)) => {
+                self.pressed = Some((layer, path[..n].to_vec(), *pt));
+                Some(resp)
+            }
+            None => {
+                self.pressed = Some((layer, path, *pt));
+                None
+            }
+        };
+        combine_response_opt(focus, resp)
+    }
+    pub fn release(&mut self, layers: &mut Layers, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        let (layer, path, _) = self.pressed.take()?;
+        let mut resp = layers.dispatch(layer, &path, &MouseEvent::Release, pt, ctx).map(|r| r.1);
+        let over = match layers.hit(pt, ctx.draw_ctx) {
+            Some((l, ref p)) => l == layer && p.starts_with(&path),
+            None => false
+        };
+        if over {
+            let click = layers.dispatch(layer, &path, &MouseEvent::Click { clicks: self.clicks }, pt, ctx);
+            resp = combine_response_opt(resp, click.map(|r| r.1));
+            if self.clicks == 2 {
+                let double = layers.dispatch(layer, &path, &MouseEvent::DoubleClick, pt, ctx);
+                resp = combine_response_opt(resp, double.map(|r| r.1));
+            }
+        }
+        resp
+    }
+    //forgets the widgets under the mouse, e.g. after the widget tree was rebuilt
+    pub fn reset(&mut self) {
+        self.hover = None;
+        self.pressed = None;
+    }
+    //sends Leave to the widgets the mouse is no longer over, deepest first,
+    //then Enter to the ones it is now over, outermost first
+    fn set_hover(&mut self, hit: Option<(Layer, WidgetPath)>, layers: &mut Layers, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        let shared = match (&self.hover, &hit) {
+            (Some((l1, p1)), Some((l2, p2))) if l1 == l2 => {
+                p1.iter().zip(p2.iter()).take_while(|(a, b)| a == b).count() + 1
+            }
+            _ => 0
+        };
+        let mut resp = None;
+        if let Some((layer, ref path)) = self.hover {
+            for n in (shared..=path.len()).rev() {
+                resp = combine_response_opt(resp, layers.send(layer, &path[..n], Phase::Bubble, &MouseEvent::Leave, pt, ctx));
+            }
+        }
+        if let Some((layer, ref path)) = hit {
+            for n in shared..=path.len() {
+                resp = combine_response_opt(resp, layers.send(layer, &path[..n], Phase::Bubble, &MouseEvent::Enter, pt, ctx));
+            }
+        }
+        self.hover = hit;
+        resp
+    }
+}
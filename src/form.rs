@@ -0,0 +1,206 @@
+extern crate nalgebra_glm;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use nalgebra_glm as glm;
+use crate::primitives::{DrawCtx, Point, Rect, RotateRect, Radians, Radii, Paint};
+use crate::render_text::{TextParams};
+use crate::widgets::{Widget, MDDoc, Wrapper, WrapperT};
+
+//the serialized value of every named field in a form, refreshed by AppState::update_fields
+//whenever a Changed message reaches the root
+#[derive(Clone)]
+pub struct FormValues(Rc<RefCell<HashMap<String, String>>>);
+
+#[allow(dead_code)]
+impl FormValues {
+    pub fn new() -> Self {
+        FormValues(Rc::new(RefCell::new(HashMap::new())))
+    }
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.0.borrow().get(name).cloned()
+    }
+    pub fn number(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(|v| v.trim().parse::<f64>().ok())
+    }
+    pub fn set(&self, name: &str, value: String) {
+        self.0.borrow_mut().insert(name.to_string(), value);
+    }
+}
+
+//names a widget so other fields can depend on its value
+pub struct Field {
+    name: String,
+    values: FormValues
+}
+
+pub fn new_field(name: &str, widget: Box<dyn Widget>, values: &FormValues) -> Box<dyn Widget> {
+    Box::new(Wrapper::new(Field { name: name.to_string(), values: values.clone() }, widget))
+}
+
+impl WrapperT for Field {
+    fn publish(&self, w: &Box<dyn Widget>) {
+        let mut tmp = MDDoc::empty();
+        w.serialize(&mut tmp);
+        self.values.set(&self.name, String::from_utf8_lossy(&tmp.body).into_owned());
+    }
+}
+
+pub type VisibleFn = Rc<dyn Fn(&FormValues) -> bool>;
+
+//shows its widget only while the rule holds for the current form values.
+//While hidden it takes no space, ignores input and isn't serialized
+pub struct Conditional {
+    values: FormValues,
+    rule: VisibleFn
+}
+
+pub fn new_conditional(widget: Box<dyn Widget>, values: &FormValues, rule: &VisibleFn) -> Box<dyn Widget> {
+    Box::new(Wrapper::new(Conditional { values: values.clone(), rule: rule.clone() }, widget))
+}
+
+//wraps every widget of a grid row in the same rule so the whole row hides together
+pub fn show_when<F: Fn(&FormValues) -> bool + 'static>(values: &FormValues, rule: F, row: Vec<Box<dyn Widget>>) -> Vec<Box<dyn Widget>> {
+    let rule: VisibleFn = Rc::new(rule);
+    row.into_iter().map(|w| new_conditional(w, values, &rule)).collect()
+}
+
+impl WrapperT for Conditional {
+    fn shown(&self) -> bool {
+        (self.rule)(&self.values)
+    }
+}
+
+pub type ComputeFn = Box<dyn Fn(&FormValues) -> Option<f64>>;
+
+//a read-only number worked out from other fields, shown as - while any input is missing
+pub struct Computed {
+    values: FormValues,
+    compute: ComputeFn,
+    decimals: usize,
+    num_chars: usize,
+    size: Point
+}
+
+impl Computed {
+    pub fn new(values: &FormValues, num_chars: usize, decimals: usize, compute: ComputeFn, ctx: &DrawCtx) -> Self {
+        let mut w = Computed { values: values.clone(), compute, decimals, num_chars, size: Point::origin() };
+        w.remeasure(ctx);
+        w
+    }
+    pub fn value(&self) -> Option<f64> {
+        (self.compute)(&self.values).filter(|v| v.is_finite())
+    }
+    fn text(&self) -> String {
+        match self.value() {
+            Some(v) => format!("{:.*}", self.decimals, v),
+            None => String::from("-")
+        }
+    }
+}
+
+//the trade numbers computed from the entry, stop, target, account and risk_pct fields.
+//Without a distance from entry to stop there is no risk to divide by
+pub fn risk_per_share(v: &FormValues) -> Option<f64> {
+    Some((v.number("entry")? - v.number("stop")?).abs()).filter(|r| *r > 0.)
+}
+
+pub fn r_multiple(v: &FormValues) -> Option<f64> {
+    let (entry, target) = (v.number("entry")?, v.number("target")?);
+    let stop = v.number("stop").filter(|s| *s != entry)?;
+    Some((target - entry) / (entry - stop))
+}
+
+pub fn position_size(v: &FormValues) -> Option<f64> {
+    let risk = v.number("account")? * v.number("risk_pct")? / 100.;
+    Some((risk / risk_per_share(v)?).floor())
+}
+
+pub fn new_computed<F: Fn(&FormValues) -> Option<f64> + 'static>(values: &FormValues, decimals: usize, compute: F, ctx: &DrawCtx) -> Box<dyn Widget> {
+    Box::new(Computed::new(values, 8, decimals, Box::new(compute), ctx))
+}
+
+impl Widget for Computed {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        self.size
+    }
+    fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
+        let pad = ctx.theme.padding.x;
+        let text = ctx.render_text.measure(&"0".repeat(self.num_chars), 1.);
+        self.size = Point::new(text.x + pad * 2., text.y);
+        self.size
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        Some(ctx.render_text.baseline(1.))
+    }
+    //drawn without a border so it doesn't look editable
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        let theme = &ctx.theme;
+        let r = Rect::new(*off, *off + self.size);
+        let bg = glm::mix(&theme.palette.widget_bg, &theme.palette.background, 0.5);
+        ctx.draw_round_rect(r.clone(), Radii::all(theme.radius), Paint::Solid(bg), None);
+        let text = self.text();
+        let m = ctx.render_text.measure(&text, 1.);
+        let c1 = Point::new(r.c2.x - theme.padding.x - m.x, r.c1.y);
+        ctx.push_clip(&r);
+        ctx.render_text.draw(&text, &TextParams::new(), &RotateRect::from_rect(Rect::new(c1, c1 + m), Radians(0.)), ctx);
+        ctx.pop_clip();
+    }
+    fn serialize(&self, buf: &mut MDDoc) {
+        buf.body.extend_from_slice(self.text().as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(fields: &[(&str, &str)]) -> FormValues {
+        let v = FormValues::new();
+        fields.iter().for_each(|(k, val)| v.set(k, val.to_string()));
+        v
+    }
+
+    #[test]
+    fn number_parses_trimmed_values() {
+        let v = values(&[("a", " 1.50 "), ("b", ""), ("c", "-"), ("d", "x")]);
+        assert_eq!(v.number("a"), Some(1.5));
+        assert_eq!(v.number("b"), None);
+        assert_eq!(v.number("c"), None);
+        assert_eq!(v.number("d"), None);
+        assert_eq!(v.number("missing"), None);
+    }
+
+    #[test]
+    fn trade_numbers() {
+        let v = values(&[("entry", "50"), ("stop", "48"), ("target", "56"), ("account", "10000"), ("risk_pct", "1")]);
+        assert_eq!(risk_per_share(&v), Some(2.));
+        assert_eq!(r_multiple(&v), Some(3.));
+        assert_eq!(position_size(&v), Some(50.));
+        //a short trade has its stop above the entry
+        let v = values(&[("entry", "48"), ("stop", "50"), ("target", "42"), ("account", "1000"), ("risk_pct", "1.5")]);
+        assert_eq!(risk_per_share(&v), Some(2.));
+        assert_eq!(r_multiple(&v), Some(3.));
+        assert_eq!(position_size(&v), Some(7.));
+    }
+
+    #[test]
+    fn entry_at_the_stop_has_no_risk() {
+        let v = values(&[("entry", "50"), ("stop", "50.00"), ("target", "56"), ("account", "10000"), ("risk_pct", "1")]);
+        assert_eq!(risk_per_share(&v), None);
+        assert_eq!(r_multiple(&v), None);
+        assert_eq!(position_size(&v), None);
+    }
+
+    #[test]
+    fn missing_inputs() {
+        let v = values(&[("entry", "50"), ("stop", "48"), ("target", "")]);
+        assert_eq!(risk_per_share(&v), Some(2.));
+        assert_eq!(r_multiple(&v), None);
+        assert_eq!(position_size(&v), None);
+        let v = values(&[("entry", "50"), ("account", "10000"), ("risk_pct", "1")]);
+        assert_eq!(risk_per_share(&v), None);
+        assert_eq!(position_size(&v), None);
+    }
+}
@@ -2,16 +2,31 @@ extern crate sdl2;
 extern crate chrono;
 
 use std::collections::{HashMap};
+use std::rc::Rc;
 use sdl2::event::Event;
-use sdl2::keyboard::{Keycode, Mod};
-use sdl2::mouse::{Cursor, SystemCursor};
+use sdl2::keyboard::{Mod};
+use sdl2::mouse::{Cursor, SystemCursor, MouseWheelDirection};
 use sdl2::video::Window;
 use std::fs::File;
+use std::path::Path;
 use std::io::{Write};
+use std::time::{Duration, Instant};
 use crate::primitives::*;
 use crate::render_text::{TextParams};
-use std::rc::Rc;
-use std::cell::RefCell;
+use crate::theme::{Theme};
+use crate::overlay::{Overlay, PopupId};
+use crate::scroll::{ScrollView};
+use crate::layout::{LayoutParams, Align, Insets, with_layout};
+use crate::events::{MouseTracker, Layers, Layer};
+use crate::choice::{new_checkbox, new_radio_group};
+use crate::number::{NumberInput, new_number_input};
+use crate::form::{FormValues, new_field, new_computed, show_when, risk_per_share, r_multiple, position_size};
+use crate::tags::{new_tag_input};
+use crate::symbols::{Watchlist, SymbolInput};
+use crate::tooltip::{Tooltips, tooltip_at, with_tooltip};
+use crate::dialog::{Dialog, Answer};
+use crate::notify::{Toasts, StatusBar, Severity};
+use crate::timer::{Timers, Timer, TimerId};
 use crate::widgets::*;
 use chrono::Datelike;
 
@@ -71,102 +86,292 @@ impl Shape {
 }
 
 pub struct AppState {
-    pub interface: WidgetGrid,
-    pub key_item: Option<HandleKeyItem>,
+    pub interface: ScrollView,
+    pub overlay: Overlay,
     pub draw_ctx: DrawCtx,
+    tracker: MouseTracker,
+    timers: Timers,
+    tooltips: Tooltips,
+    toasts: Toasts,
+    status: StatusBar,
+    //an entry waiting for the user to confirm overwriting the existing file
+    pending_write: Option<PendingWrite>,
     window: Window,
     cursors: CursorMap,
     needs_draw: bool,
+    mouse: Point,
 }
 
-pub type CallbackFn = Rc<dyn Fn(&mut AppState)>;
+const INTERFACE_OFFSET: (f32, f32) = (15., 15.);
 
-pub trait HandleKey {
-    fn handle_key_down(&mut self, kc: &Keycode, rt: &EventCtx) -> Option<WidgetResponse>;
+fn interface_offset() -> Point {
+    Point::new(INTERFACE_OFFSET.0, INTERFACE_OFFSET.1)
 }
 
-pub type HandleKeyItem = Rc<RefCell<dyn HandleKey>>;
+//the interface is inset by INTERFACE_OFFSET on every side and sits above the status bar
+fn interface_size(ctx: &DrawCtx) -> Point {
+    let size = ctx.viewport - interface_offset() * 2. - Point::new(0., StatusBar::height(ctx));
+    Point::new(size.x.max(0.), size.y.max(0.))
+}
 
-const INTERFACE_OFFSET: (f32, f32) = (15., 15.);
+//user override for the ui scale on top of the display's pixel ratio, e.g. MD_UI_SCALE=1.5
+const UI_SCALE_VAR: &str = "MD_UI_SCALE";
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 4.;
+
+fn drawable_size(window: &Window) -> Point {
+    let (w, h) = window.drawable_size();
+    Point::new(w as f32, h as f32)
+}
+
+//device pixels per window coordinate, > 1 on HiDPI displays
+fn pixel_ratio(window: &Window) -> f32 {
+    let (w, _) = window.size();
+    if w == 0 { 1. } else { window.drawable_size().0 as f32 / w as f32 }
+}
+
+pub fn ui_scale(window: &Window) -> f32 {
+    let user_scale = std::env::var(UI_SCALE_VAR).ok()
+        .and_then(|s| s.parse::<f32>().ok())
+        .filter(|s| s.is_finite() && *s > 0.)
+        .map(|s| s.max(MIN_UI_SCALE).min(MAX_UI_SCALE))
+        .unwrap_or(1.);
+    pixel_ratio(window) * user_scale
+}
 
 impl AppState {
-    pub fn new(viewport: &Point, window: Window) -> AppState {
-        let draw_ctx = DrawCtx::new(viewport);
-        let interface = new_form(&draw_ctx);
-        AppState {
+    pub fn new(window: Window) -> AppState {
+        //a broken theme falls back to the light one and a missing watchlist to an empty one,
+        //the symbol field then takes anything. Both are reported once the window is up
+        let mut problems = Vec::new();
+        let theme = Theme::load().unwrap_or_else(|e| {
+            problems.push((Severity::Error, e));
+            Theme::light()
+        });
+        let watchlist = Watchlist::load(&watchlist_path()).unwrap_or_else(|e| {
+            problems.push((Severity::Warning, e));
+            Watchlist::empty()
+        });
+        let draw_ctx = DrawCtx::new(&drawable_size(&window), ui_scale(&window), theme);
+        let form = new_form(&Rc::new(watchlist), &draw_ctx);
+        let mut interface = ScrollView::new(Box::new(form), interface_size(&draw_ctx), &draw_ctx);
+        //conditional rows read the published values, so publish before the first layout
+        walk(&interface, &mut |w| w.publish());
+        interface.remeasure(&draw_ctx);
+        let mut app = AppState {
             draw_ctx,
             interface,
+            overlay: Overlay::new(),
             window,
-            key_item: None, 
+            tracker: MouseTracker::new(),
+            timers: Timers::new(),
+            tooltips: Tooltips::new(),
+            toasts: Toasts::new(),
+            status: StatusBar::new(),
+            pending_write: None,
+            mouse: Point::origin(),
             cursors: CursorMap::new(),
             needs_draw: true
+        };
+        for (severity, e) in problems {
+            app.notify(severity, &e);
         }
+        app
     }
-    pub fn handle_response(&mut self, resp: &Option<WidgetResponse>) {
-        if let Some((status, ref cb)) = resp {
-            if *status & (WidgetStatus::REDRAW) != WidgetStatus::FINE {
+    pub fn handle_response(&mut self, resp: Option<WidgetResponse>) {
+        if let Some(WidgetResponse { status, msgs }) = resp {
+            if status & (WidgetStatus::REDRAW) != WidgetStatus::FINE {
                 self.needs_draw = true;
             }
-            if *status & (WidgetStatus::REDRAW) != WidgetStatus::FINE {
+            if status & (WidgetStatus::REDRAW) != WidgetStatus::FINE {
                 self.interface.remeasure(&self.draw_ctx);
             }
-            cb(self);
+            for msg in msgs {
+                self.handle_message(msg);
+            }
+        }
+    }
+    //refreshes the values computed fields are worked out from, and lays the
+    //form out again if that showed or hid any conditional fields
+    pub fn update_fields(&mut self) {
+        let shown = |root: &dyn Widget| {
+            let mut v = Vec::new();
+            walk(root, &mut |w| v.push(w.visible()));
+            v
+        };
+        let before = shown(&self.interface);
+        walk(&self.interface, &mut |w| w.publish());
+        if shown(&self.interface) != before {
+            self.interface.deselect();
+            self.interface.remeasure(&self.draw_ctx);
+            self.tracker.reset();
+        }
+        self.needs_draw = true;
+    }
+    //messages no widget consumed on the way up
+    fn handle_message(&mut self, msg: Message) {
+        match msg {
+            Message::OpenPopup(popup) => self.overlay.open(popup),
+            Message::ClosePopup(id) => {
+                let resp = self.overlay.close(id);
+                self.handle_response(resp);
+            }
+            Message::Submit => self.submit(),
+            Message::Changed => self.update_fields(),
+            Message::DialogClosed(id, answer) => self.dialog_closed(id, answer),
+            Message::Notify(severity, text) => self.notify(severity, &text),
+            Message::StartTimer(timer) => { self.start_timer(timer); }
+            Message::CancelTimer(id) => self.cancel_timer(id),
+            Message::Answer(_) => {}
         }
     }
+    //maps window coordinates from sdl to logical pixels
+    fn to_logical(&self, x: i32, y: i32) -> Point {
+        Point::new(x as f32, y as f32) * (pixel_ratio(&self.window) / self.draw_ctx.scale)
+    }
     pub fn handle_mouse_event(&mut self, ev: &Event, _: &Mod) {
         let mut resp: Option<WidgetResponse> = None;
         match *ev {
-            Event::MouseButtonDown { mouse_btn, x, y, .. } => {
+            Event::MouseButtonDown { mouse_btn, clicks, x, y, .. } => {
                 if mouse_btn == sdl2::mouse::MouseButton::Left {
-                    let pt = Point{x: x as f32 - INTERFACE_OFFSET.0 ,y: y as f32 - INTERFACE_OFFSET.1 };
+                    let pt = self.to_logical(x, y);
                     let mut use_cursor = SystemCursor::Arrow;
                     let mut event_ctx = EventCtx {
                         draw_ctx: &self.draw_ctx,
-                        cursor: &mut use_cursor
+                        cursor: &mut use_cursor,
+                        mouse: pt
                     };
-                    resp = self.interface.click(&pt, &mut event_ctx);
-                    if resp.is_none() {
-                        resp = self.interface.deselect();
-                    }
+                    let mut layers = Layers { interface: &mut self.interface, origin: interface_offset(), overlay: &self.overlay };
+                    resp = self.tracker.press(&mut layers, &pt, clicks, &mut event_ctx);
+                    self.needs_draw |= self.tooltips.click(&mut self.overlay);
                     self.cursors.get(&use_cursor).set();
                 }
             } 
-            Event::MouseButtonUp{mouse_btn, .. } => {
+            Event::MouseButtonUp{ mouse_btn, x, y, .. } => {
                 if mouse_btn == sdl2::mouse::MouseButton::Left {
+                    let pt = self.to_logical(x, y);
+                    let mut use_cursor = SystemCursor::Arrow;
+                    let mut event_ctx = EventCtx {
+                        draw_ctx: &self.draw_ctx,
+                        cursor: &mut use_cursor,
+                        mouse: pt
+                    };
+                    let mut layers = Layers { interface: &mut self.interface, origin: interface_offset(), overlay: &self.overlay };
+                    resp = self.tracker.release(&mut layers, &pt, &mut event_ctx);
                 }
             }
             Event::MouseMotion{ x, y, ..} => {
-                let pt = Point{x: x as f32,y: y as f32};
+                let pt = self.to_logical(x, y);
+                self.mouse = pt;
                 let mut use_cursor = SystemCursor::Arrow;
                 let mut event_ctx = EventCtx {
                     draw_ctx: &self.draw_ctx,
-                    cursor: &mut use_cursor
+                    cursor: &mut use_cursor,
+                    mouse: pt
+                };
+                let mut layers = Layers { interface: &mut self.interface, origin: interface_offset(), overlay: &self.overlay };
+                resp = self.tracker.motion(&mut layers, &pt, &mut event_ctx);
+                let tooltip = match layers.hit(&pt, &self.draw_ctx) {
+                    Some((Layer::Interface, path)) => tooltip_at(&self.interface, &path),
+                    Some((Layer::Popup(id), path)) => self.overlay.get(id).and_then(|p| tooltip_at(&*p.widget.borrow(), &path)),
+                    None => None
                 };
-                resp = self.interface.hover(&pt, &mut event_ctx);
+                self.needs_draw |= self.tooltips.hover(tooltip, &mut self.overlay);
                 self.cursors.get(&use_cursor).set();
             }
+            Event::MouseWheel{ x, y, direction, ..} => {
+                let flip = if direction == MouseWheelDirection::Flipped { -1. } else { 1. };
+                let delta = Point::new(x as f32, -y as f32) * flip;
+                let pt = self.mouse;
+                let mut use_cursor = SystemCursor::Arrow;
+                let mut event_ctx = EventCtx {
+                    draw_ctx: &self.draw_ctx,
+                    cursor: &mut use_cursor,
+                    mouse: pt
+                };
+                resp = match self.overlay.scroll(&pt, &delta, &mut event_ctx) {
+                    Some(r) => r,
+                    None if self.overlay.modal().is_some() => None,
+                    None => self.interface.scroll(&(pt - interface_offset()), &delta, &mut event_ctx)
+                };
+            }
             _ => {}
         }
-        self.handle_response(&resp);
+        self.handle_response(resp);
     }
     pub fn handle_keyboard_event(&mut self, ev: &Event) {
         let mut resp: Option<WidgetResponse> = None;
-        if let Some(ref key_item) = self.key_item {
+        if let Event::KeyDown { keycode: Some(keycode), .. } = *ev {
             let mut use_cursor = SystemCursor::Arrow;
-            if let Event::KeyDown { keycode: Some(keycode), .. } = *ev {
-                let mut event_ctx = EventCtx {
-                    draw_ctx: &self.draw_ctx,
-                    cursor: &mut use_cursor
-                };
-                resp = key_item.borrow_mut().handle_key_down(&keycode, &mut event_ctx);
-            }
+            let mut event_ctx = EventCtx {
+                draw_ctx: &self.draw_ctx,
+                cursor: &mut use_cursor,
+                mouse: self.mouse
+            };
+            //a modal dialog takes the keys from everything behind it
+            resp = match self.overlay.modal().and_then(|id| self.overlay.get(id)) {
+                Some(p) => p.widget.borrow_mut().key_down(&keycode, &mut event_ctx),
+                None => self.interface.key_down(&keycode, &mut event_ctx)
+            };
+        }
+        self.handle_response(resp);
+    }
+    //runs before every frame for things that happen without an event
+    pub fn tick(&mut self) {
+        for resp in self.timers.fire() {
+            self.handle_response(Some(resp));
         }
-        self.handle_response(&resp);
+        self.needs_draw |= self.tooltips.tick(&self.mouse, &mut self.overlay, &self.draw_ctx);
+        self.needs_draw |= self.toasts.tick();
+    }
+    pub fn start_timer(&mut self, timer: Timer) -> TimerId {
+        self.timers.start(timer)
+    }
+    pub fn cancel_timer(&mut self, id: TimerId) {
+        self.timers.cancel(id);
+    }
+    //how long the event loop can wait for events before the next tick, None when nothing is scheduled
+    pub fn timeout(&self) -> Option<Duration> {
+        let due = [self.timers.next_due(), self.tooltips.next_due(), self.toasts.next_due()];
+        due.iter().filter_map(|d| *d).min()
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+    //shows a toast and puts the text in the status bar, widgets can send Message::Notify instead
+    pub fn notify(&mut self, severity: Severity, text: &str) {
+        self.toasts.push(severity, text, &self.draw_ctx);
+        self.status.set(severity, text);
+        self.needs_draw = true;
+    }
+    //the window may have moved to a display with a different pixel ratio,
+    //so the scale is recomputed along with the drawable size
+    pub fn resize(&mut self) {
+        self.draw_ctx.set_scale(ui_scale(&self.window));
+        self.draw_ctx.set_viewport(&drawable_size(&self.window));
+        self.interface.set_size(interface_size(&self.draw_ctx));
+        self.interface.remeasure(&self.draw_ctx);
+        self.needs_draw = true;
+    }
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.draw_ctx.set_theme(theme);
+        self.interface.remeasure(&self.draw_ctx);
+        self.needs_draw = true;
+    }
+    pub fn toggle_theme(&mut self) {
+        let theme = if self.draw_ctx.theme.name == "dark" { Theme::light() } else { Theme::dark() };
+        self.set_theme(theme);
     }
     pub fn render(&mut self) {
         if self.needs_draw {
-            unsafe { gl::Clear(gl::COLOR_BUFFER_BIT); }
-            self.interface.draw(&Point::new(INTERFACE_OFFSET.0, INTERFACE_OFFSET.1), &self.draw_ctx);
+            let bg = self.draw_ctx.theme.palette.background;
+            unsafe {
+                gl::ClearColor(bg[0], bg[1], bg[2], bg[3]);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+            }
+            self.interface.draw(&interface_offset(), &self.draw_ctx);
+            self.overlay.draw(&self.draw_ctx);
+            self.status.draw(&self.draw_ctx);
+            let corner = self.draw_ctx.viewport - interface_offset() - Point::new(0., StatusBar::height(&self.draw_ctx));
+            self.toasts.draw(&corner, &self.draw_ctx);
             self.window.gl_swap_window();
             self.needs_draw = false;
         }
@@ -175,26 +380,76 @@ impl AppState {
     title: "AMD - Trend Extension"
     date: 2019-11-26T01:00:00-05:00
     draft: false*/
-    pub fn serialize(&self) {
+    //renders the form to the markdown file for its symbol, returning the path and contents
+    pub fn serialize(&self) -> (String, Vec<u8>) {
         let mut md = MDDoc::empty();
         self.interface.serialize(&mut md);
         let path = format!("/opt/blocktradingsystems/tradelog/content/holdings/{}/{}.md", 
                            md.portfolio,
                            md.title.symbol);
-        println!("{}", path);
         let date = chrono::Local::now();
-        let title = format!("---\ntitle: \"{} - {}\"\ndate: {}\ndraft: false\n---\n# Entry\n", md.title.symbol, md.title.strategy, date.to_rfc3339());
+        //backslashes first so the ones added for quotes aren't doubled
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let patterns = if md.patterns.is_empty() { String::from(" []\n") }
+            else { md.patterns.iter().map(|p| format!("\n  - {}", quote(p))).collect::<String>() + "\n" };
+        let mut info = String::new();
+        if let Some(name) = &md.title.name {
+            info += &format!("name: {}\n", quote(name));
+        }
+        if let Some(sector) = &md.title.sector {
+            info += &format!("sector: {}\n", quote(sector));
+        }
+        let title = format!("---\ntitle: {}\ndate: {}\n{}patterns:{}draft: false\n---\n# Entry\n", quote(&format!("{} - {}", md.title.symbol, md.title.strategy)),
+                            date.to_rfc3339(), info, patterns);
         let addenda = format!("\n# Log\n* {}/{}/{}", 
                               date.month(), date.day(), date.year());
+        let mut contents = title.into_bytes();
+        contents.extend_from_slice(&md.body);
+        contents.extend_from_slice(addenda.as_bytes());
+        (path, contents)
+    }
+    //an existing entry is only replaced after the user confirms
+    fn submit(&mut self) {
+        let (path, contents) = self.serialize();
+        if !Path::new(&path).exists() {
+            return self.write_entry(&path, &contents)
+        }
+        let dialog = Dialog::confirm("Replace entry?", &format!("{} already exists. Do you want to overwrite it?", path), Answer::No, &self.draw_ctx);
+        self.pending_write = Some((dialog.id(), path, contents));
+        self.overlay.open(dialog.popup());
+    }
+    fn write_entry(&mut self, path: &str, contents: &[u8]) {
         let write_file = || {
-            let mut file = File::create(&path)?;
-            file.write(&title.as_bytes())?;
-            file.write(&md.body)?;
-            file.write(&addenda.as_bytes())
+            let mut file = File::create(path)?;
+            file.write_all(contents)
         };
         match write_file() {
-            Ok(_) => { println!("Wrote to file {}", path); }
-            Err(e) => { println!("Error writing to file {:?}", e); }
+            Ok(_) => self.notify(Severity::Success, &format!("Wrote to file {}", path)),
+            Err(e) => {
+                self.status.set(Severity::Error, &format!("Couldn't save {}", path));
+                let dialog = Dialog::message("Couldn't save entry", &format!("Error writing {}: {}", path, e), &self.draw_ctx);
+                self.overlay.open(dialog.popup());
+            }
+        }
+    }
+    fn dialog_closed(&mut self, id: PopupId, answer: Answer) {
+        if let Some((path, contents)) = take_answered(&mut self.pending_write, id, &answer) {
+            self.write_entry(&path, &contents);
+        }
+    }
+}
+
+//an entry waiting for the overwrite dialog with this id: path and contents
+type PendingWrite = (PopupId, String, Vec<u8>);
+
+//the write to do now if this dialog was the one asking and the answer was Yes.
+//Any answer to it drops the pending write, other dialogs leave it alone
+fn take_answered(pending: &mut Option<PendingWrite>, id: PopupId, answer: &Answer) -> Option<(String, Vec<u8>)> {
+    match pending.take() {
+        Some((dialog, path, contents)) if dialog == id => Some((path, contents)).filter(|_| *answer == Answer::Yes),
+        other => {
+            *pending = other;
+            None
         }
     }
 }
@@ -225,19 +480,48 @@ Level:
     LEVEL_E
  */
 
-pub fn new_form(ctx: &DrawCtx) -> WidgetGrid {
-    let mut form = WidgetGrid::new(Point::new(10., 10.)).builder(ctx);
-    form += vec![new_label("Symbol:"), new_serialize::<SymbolSerializer>(new_textbox(6, "", ctx))];
-    form += vec![new_label("Strategy:"), new_serialize::<StrategySerializer>(new_dropdown( 
+//ticker, name, sector of every symbol the Symbol field suggests
+const WATCHLIST_PATH: &str = "/opt/blocktradingsystems/tradelog/data/watchlist.csv";
+//user override for the watchlist, e.g. MD_UI_WATCHLIST=watchlist.csv
+const WATCHLIST_VAR: &str = "MD_UI_WATCHLIST";
+
+fn watchlist_path() -> String {
+    std::env::var(WATCHLIST_VAR).unwrap_or_else(|_| WATCHLIST_PATH.to_string())
+}
+
+//patterns offered by the Pattern field, others can still be typed in
+const PATTERNS: [&str; 12] = [
+    "Pennant",
+    "Flag",
+    "Bull Flag",
+    "Bear Flag",
+    "Wedge",
+    "Triangle",
+    "Cup and Handle",
+    "Double Bottom",
+    "Double Top",
+    "Head and Shoulders",
+    "Channel",
+    "Breakout",
+];
+
+pub fn new_form(watchlist: &Rc<Watchlist>, ctx: &DrawCtx) -> WidgetGrid {
+    let values = FormValues::new();
+    let mut form = WidgetGrid::new(ctx.theme.spacing).align(Align::Start, Align::Baseline).builder(ctx);
+    form += vec![new_label("Symbol:"), new_serialize::<SymbolSerializer>(Box::new(SymbolInput::new(watchlist, 6, ctx).describe(true)))];
+    form += vec![with_tooltip(new_label("Strategy:"), "The setup traded. The LEVEL_ strategies enter off a pivot level, picked in the Level field"), new_field("strategy", new_serialize::<StrategySerializer>(new_dropdown( 
         vec![
             "Trend",
             "Mean Reversion",
-        ], 0, ctx))];
+            "LEVEL_E Extension Pivot Re-Entry",
+            "LEVEL_D Pivot Entry",
+        ], 0, ctx)), &values)];
     form += vec![new_label("Date:"), Box::new(DateWidget::new(ctx))];
-    form += vec![new_label("Volume:"), new_dropdown(vec![ "Yes", "No"], 0, ctx)];
-    form += vec![new_label("Gap:"), new_dropdown(vec![ "Yes", "No"], 0, ctx)];
-    form += vec![new_label("Range:"), new_dropdown(vec![ "Yes", "No"], 0, ctx)];
-    form += vec![new_label("Level:"), new_h_list(vec![new_dropdown(
+    form += vec![with_tooltip(new_label("Volume:"), "Volume was above its recent average on the entry day"), new_checkbox(true, ctx)];
+    form += vec![with_tooltip(new_label("Gap:"), "The stock opened with a gap from the previous close"), new_checkbox(true, ctx)];
+    form += vec![with_tooltip(new_label("Range:"), "The day's high to low range was wider than its recent average"), new_checkbox(true, ctx)];
+    //levels only apply to the pivot strategies
+    form += show_when(&values, |v| v.get("strategy").map_or(false, |s| s.starts_with("LEVEL_")), vec![with_tooltip(new_label("Level:"), "The pivot level the entry was taken from, only used by the LEVEL_ strategies"), new_h_list(vec![new_dropdown(
         vec![
             "LEVEL_C",
             "LEVEL_A",
@@ -246,23 +530,35 @@ pub fn new_form(ctx: &DrawCtx) -> WidgetGrid {
             "LEVEL_E",
             "LEVEL_F",
             "LEVEL_G",
-        ], 0, ctx), new_dropdown(vec![" ", "Minus"], 0, ctx)], 10, ctx)];
-    form += vec![new_label("Pattern:"), new_textbox(30, "", ctx)];
+        ], 0, ctx), new_dropdown(vec![" ", "Minus"], 0, ctx)], 10, ctx)]);
+    form += vec![new_label("Entry:"), new_field("entry", new_number_input(None, 8, 2, ctx), &values)];
+    form += vec![new_label("Stop:"), new_field("stop", new_number_input(None, 8, 2, ctx), &values)];
+    form += vec![new_label("Target:"), new_field("target", new_number_input(None, 8, 2, ctx), &values)];
+    form += vec![new_label("Account:"), new_field("account", new_number_input(None, 8, 2, ctx), &values)];
+    form += vec![new_label("Risk %:"), new_field("risk_pct",
+        Box::new(NumberInput::new(Some(1.), 8, ctx).range(0., 100.).step(0.25).decimals(2)), &values)];
+    form += vec![new_label("Risk/Share:"), new_computed(&values, 2, risk_per_share, ctx)];
+    form += vec![with_tooltip(new_label("R Multiple:"), "Reward to risk: the distance from entry to target over the distance from entry to stop"), new_computed(&values, 2, r_multiple, ctx)];
+    form += vec![with_tooltip(new_label("Position Size:"), "Shares to buy so that getting stopped out loses Risk % of the account"), new_computed(&values, 0, position_size, ctx)];
+    form += vec![new_label("Shares:"), Box::new(NumberInput::new(None, 8, ctx).range(0., 1e9).step(10.))];
+    form += vec![with_tooltip(new_label("Pattern:"), "Chart patterns at entry. Pick from the list or type a new one and press Return"), with_layout(new_tag_input(PATTERNS.to_vec(), 30, ctx),
+        LayoutParams::new().align(Align::Stretch, Align::Baseline).grow(1.), ctx)];
     form += vec![
         new_label("Portfolio:"), 
-        new_serialize::<PortfolioSerializer>(new_dropdown(vec![ "A", "B"], 0, ctx))];
-    let border = Border::new(Point::new(5., 5.), rgb_to_f32(0, 0, 0));
-    let mut submit = Button::new(border, rgb_to_f32(0, 255, 255), 
-        just_cb(Rc::new(|app: &mut AppState| app.serialize()))
-    ).builder(ctx);
+        new_serialize::<PortfolioSerializer>(new_radio_group(vec![ "A", "B"], 0, ctx))];
+    let mut submit = Button::new(Message::Submit).builder(ctx);
     submit += Label::new("Submit", None, None, None, TextParams::new());
-    form += vec![new_serialize::<SkipSerializer>(submit.widget())];
+    let submit = with_layout(submit.widget(), LayoutParams::new().col_span(2).align(Align::End, Align::Start)
+        .margin(Insets::xy(0., 5.)), ctx);
+    form += vec![new_serialize::<SkipSerializer>(submit)];
     form.get()
 }
 
 pub struct EventCtx<'a> {
     pub draw_ctx: &'a DrawCtx,
-    pub cursor: &'a mut SystemCursor
+    pub cursor: &'a mut SystemCursor,
+    //mouse position in logical window coordinates
+    pub mouse: Point
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -270,3 +566,38 @@ enum ClickResponse {
     Clicked,
     NotClicked
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(id: PopupId) -> Option<PendingWrite> {
+        Some((id, String::from("entry.md"), b"* Symbol: AMD".to_vec()))
+    }
+
+    #[test]
+    fn other_dialogs_keep_the_pending_write() {
+        let (asking, other) = (PopupId::new(), PopupId::new());
+        let mut p = pending(asking);
+        assert_eq!(take_answered(&mut p, other, &Answer::Yes), None);
+        assert_eq!(p, pending(asking));
+        assert_eq!(take_answered(&mut None, other, &Answer::Yes), None);
+    }
+
+    #[test]
+    fn no_discards_the_pending_write() {
+        let id = PopupId::new();
+        let mut p = pending(id);
+        assert_eq!(take_answered(&mut p, id, &Answer::No), None);
+        assert_eq!(p, None);
+    }
+
+    #[test]
+    fn yes_writes() {
+        let id = PopupId::new();
+        let mut p = pending(id);
+        assert_eq!(take_answered(&mut p, id, &Answer::Yes), Some((String::from("entry.md"), b"* Symbol: AMD".to_vec())));
+        assert_eq!(p, None);
+        assert_eq!(take_answered(&mut p, id, &Answer::Yes), None);
+    }
+}
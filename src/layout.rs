@@ -0,0 +1,312 @@
+use std::f32;
+use crate::primitives::{DrawCtx, Point, Rect};
+use crate::widgets::{Widget, WidgetIterT, MDDoc};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Insets {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32
+}
+
+#[allow(dead_code)]
+impl Insets {
+    pub fn new(top: f32, right: f32, bottom: f32, left: f32) -> Self {
+        Insets { top, right, bottom, left }
+    }
+    pub fn all(v: f32) -> Self {
+        Insets::new(v, v, v, v)
+    }
+    pub fn xy(x: f32, y: f32) -> Self {
+        Insets::new(y, x, y, x)
+    }
+    pub fn zero() -> Self {
+        Insets::all(0.)
+    }
+    pub fn top_left(&self) -> Point {
+        Point::new(self.left, self.top)
+    }
+    //total horizontal and vertical space taken by the insets
+    pub fn size(&self) -> Point {
+        Point::new(self.left + self.right, self.top + self.bottom)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    Stretch,
+    //lines up the first baseline of every widget in a row, same as Start horizontally
+    Baseline
+}
+
+//how a widget wants to be placed in the cell its container gives it
+#[derive(Clone, PartialEq, Debug)]
+pub struct LayoutParams {
+    pub margin: Insets,
+    pub padding: Insets,
+    pub min_size: Point,
+    pub max_size: Point,
+    pub h_align: Align,
+    pub v_align: Align,
+    pub col_span: usize,
+    //share of the leftover space along a list, or of a grid's width, this widget takes
+    pub grow: f32
+}
+
+impl Default for LayoutParams {
+    fn default() -> Self {
+        LayoutParams {
+            margin: Insets::zero(),
+            padding: Insets::zero(),
+            min_size: Point::origin(),
+            max_size: Point::new(f32::INFINITY, f32::INFINITY),
+            h_align: Align::Start,
+            v_align: Align::Start,
+            col_span: 1,
+            grow: 0.
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl LayoutParams {
+    pub fn new() -> Self {
+        LayoutParams::default()
+    }
+    pub fn margin(mut self, margin: Insets) -> Self {
+        self.margin = margin;
+        self
+    }
+    pub fn padding(mut self, padding: Insets) -> Self {
+        self.padding = padding;
+        self
+    }
+    pub fn min_size(mut self, min_size: Point) -> Self {
+        self.min_size = min_size;
+        self
+    }
+    pub fn max_size(mut self, max_size: Point) -> Self {
+        self.max_size = max_size;
+        self
+    }
+    pub fn align(mut self, h_align: Align, v_align: Align) -> Self {
+        self.h_align = h_align;
+        self.v_align = v_align;
+        self
+    }
+    pub fn col_span(mut self, col_span: usize) -> Self {
+        self.col_span = col_span.max(1);
+        self
+    }
+    pub fn grow(mut self, grow: f32) -> Self {
+        self.grow = grow;
+        self
+    }
+    pub fn stretch(self) -> Self {
+        self.align(Align::Stretch, Align::Stretch)
+    }
+}
+
+//what a container needs to know about a child to lay it out,
+//hidden children take no space
+pub struct CellInfo {
+    pub size: Point,
+    pub baseline: Option<f32>,
+    pub params: LayoutParams,
+    pub visible: bool
+}
+
+impl CellInfo {
+    pub fn new(w: &Box<dyn Widget>, default: &LayoutParams, ctx: &DrawCtx) -> Self {
+        let visible = w.visible();
+        CellInfo {
+            size: if visible { w.measure(ctx) } else { Point::origin() },
+            baseline: if visible { w.baseline(ctx) } else { None },
+            params: w.layout_params().unwrap_or(default).clone(),
+            visible
+        }
+    }
+    //space above and below the baseline, None if the widget isn't baseline aligned
+    pub fn ascent_descent(&self) -> Option<(f32, f32)> {
+        match (self.params.v_align, self.baseline) {
+            (Align::Baseline, Some(b)) => Some((b, self.size.y - b)),
+            _ => None
+        }
+    }
+}
+
+//height of a row of cells taking baseline alignment into account,
+//and the baseline position within the row
+pub fn row_extent(cells: &[&CellInfo]) -> (f32, f32) {
+    let (mut ascent, mut descent, mut height): (f32, f32, f32) = (0., 0., 0.);
+    for c in cells {
+        height = height.max(c.size.y);
+        if let Some((a, d)) = c.ascent_descent() {
+            ascent = ascent.max(a);
+            descent = descent.max(d);
+        }
+    }
+    (height.max(ascent + descent), ascent)
+}
+
+fn align_in(align: Align, start: f32, avail: f32, size: f32) -> (f32, f32) {
+    match align {
+        Align::Start | Align::Baseline => (start, size),
+        Align::Center => (start + (avail - size) / 2., size),
+        Align::End => (start + avail - size, size),
+        Align::Stretch => (start, avail.max(size))
+    }
+}
+
+//rect of a widget within its cell, row_baseline is where the row's baseline is from the top of the cell
+pub fn place(cell_info: &CellInfo, cell: &Rect, row_baseline: f32) -> Rect {
+    let (size, params) = (&cell_info.size, &cell_info.params);
+    let (x, w) = align_in(params.h_align, cell.c1.x, cell.width(), size.x);
+    let (y, h) = match cell_info.ascent_descent() {
+        Some((ascent, _)) => (cell.c1.y + row_baseline - ascent, size.y),
+        None => align_in(params.v_align, cell.c1.y, cell.height(), size.y)
+    };
+    Rect::new(Point::new(x, y), Point::new(x + w, y + h))
+}
+
+//splits extra space between the entries with a positive weight, in proportion to it
+pub fn distribute(extra: f32, weights: &[f32]) -> Vec<f32> {
+    let total: f32 = weights.iter().filter(|w| **w > 0.).sum();
+    weights.iter().map(|w| if total > 0. && *w > 0. && extra > 0. { extra * w / total } else { 0. }).collect()
+}
+
+//wraps a widget with margins, padding, size limits and its own placement params
+pub struct Layout {
+    params: LayoutParams,
+    child: [Box<dyn Widget>; 1],
+    rect: [Rect; 1],
+    size: Point
+}
+
+impl Layout {
+    pub fn new(child: Box<dyn Widget>, params: LayoutParams, ctx: &DrawCtx) -> Self {
+        let mut layout = Layout { params, child: [child], rect: [Rect::empty()], size: Point::origin() };
+        let m = layout.child[0].measure(ctx);
+        layout.fit(m);
+        layout
+    }
+    fn fit(&mut self, child_size: Point) {
+        let p = &self.params;
+        let content = child_size + p.padding.size();
+        let content = Point::new(
+            content.x.max(p.min_size.x).min(p.max_size.x),
+            content.y.max(p.min_size.y).min(p.max_size.y));
+        let inner = content - p.padding.size();
+        let c1 = p.margin.top_left() + p.padding.top_left();
+        self.rect[0] = Rect::new(c1, c1 + Point::new(inner.x.max(0.), inner.y.max(0.)));
+        self.size = content + p.margin.size();
+    }
+}
+
+pub fn with_layout(child: Box<dyn Widget>, params: LayoutParams, ctx: &DrawCtx) -> Box<dyn Widget> {
+    Box::new(Layout::new(child, params, ctx))
+}
+
+impl WidgetIterT for Layout {
+    type Child = Box<dyn Widget>;
+    fn add(&mut self, item: Self::Child, ctx: &DrawCtx) {
+        let m = item.measure(ctx);
+        self.child[0] = item;
+        self.fit(m);
+    }
+    fn widgets_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item=&'a mut Box<dyn Widget>> + 'a> {
+        Box::new(self.child.iter_mut())
+    }
+    fn widgets_plus_rects<'a>(&'a self) -> Box<dyn Iterator<Item=(&'a Box<dyn Widget>, &'a Rect)> + 'a> {
+        Box::new(self.child.iter().zip(self.rect.iter()))
+    }
+    fn widgets_plus_rects_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item=(&'a mut Box<dyn Widget>, &'a mut Rect)> + 'a> {
+        Box::new(self.child.iter_mut().zip(self.rect.iter_mut()))
+    }
+    fn measure_items(&self, _: &DrawCtx) -> Point {
+        self.size
+    }
+    fn remeasure_items(&mut self, ctx: &DrawCtx) -> Point {
+        let m = self.child[0].remeasure(ctx);
+        self.fit(m);
+        self.size
+    }
+    fn arrange_items(&mut self, size: &Point, ctx: &DrawCtx) {
+        let p = &self.params;
+        let outer = Point::new(size.x.max(self.size.x), size.y.max(self.size.y));
+        let inner = outer - p.margin.size() - p.padding.size();
+        let inner = Point::new(inner.x.min(p.max_size.x), inner.y.min(p.max_size.y));
+        self.child[0].arrange(&inner, ctx);
+        let m = self.child[0].measure(ctx);
+        self.fit(Point::new(m.x.max(inner.x), m.y.max(inner.y)));
+    }
+    fn params(&self) -> Option<&LayoutParams> {
+        Some(&self.params)
+    }
+    fn serialize_items(&self, buf: &mut MDDoc) {
+        self.child[0].serialize(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(w: f32, h: f32, baseline: Option<f32>, params: LayoutParams) -> CellInfo {
+        CellInfo { size: Point::new(w, h), baseline, params, visible: true }
+    }
+
+    fn corners(r: &Rect) -> (Point, Point) {
+        (r.c1, r.c2)
+    }
+
+    #[test]
+    fn distribute_by_weight() {
+        assert_eq!(distribute(90., &[1., 2., 0.]), vec![30., 60., 0.]);
+        assert_eq!(distribute(10., &[0., -1.]), vec![0., 0.]);
+        assert_eq!(distribute(-5., &[1., 1.]), vec![0., 0.]);
+        assert!(distribute(10., &[]).is_empty());
+    }
+
+    #[test]
+    fn place_aligns_in_the_cell() {
+        let area = Rect::new(Point::new(10., 10.), Point::new(110., 50.));
+        let at = |h: Align, v: Align| corners(&place(&cell(20., 10., None, LayoutParams::new().align(h, v)), &area, 0.));
+        assert_eq!(at(Align::Start, Align::Start), (Point::new(10., 10.), Point::new(30., 20.)));
+        assert_eq!(at(Align::Center, Align::Center), (Point::new(50., 25.), Point::new(70., 35.)));
+        assert_eq!(at(Align::End, Align::End), (Point::new(90., 40.), Point::new(110., 50.)));
+        assert_eq!(at(Align::Stretch, Align::Stretch), (Point::new(10., 10.), Point::new(110., 50.)));
+    }
+
+    #[test]
+    fn stretch_never_shrinks() {
+        let area = Rect::new(Point::origin(), Point::new(10., 10.));
+        let r = place(&cell(20., 5., None, LayoutParams::new().stretch()), &area, 0.);
+        assert_eq!(corners(&r), (Point::origin(), Point::new(20., 10.)));
+    }
+
+    #[test]
+    fn baselines_line_up() {
+        let baseline = LayoutParams::new().align(Align::Start, Align::Baseline);
+        let big = cell(40., 30., Some(24.), baseline.clone());
+        let small = cell(20., 12., Some(9.), baseline);
+        let plain = cell(10., 5., None, LayoutParams::new());
+        let (height, row_baseline) = row_extent(&[&big, &small, &plain]);
+        assert_eq!((height, row_baseline), (30., 24.));
+        let area = Rect::new(Point::new(0., 100.), Point::new(50., 100. + height));
+        assert_eq!(place(&small, &area, row_baseline).c1, Point::new(0., 115.));
+        assert_eq!(place(&plain, &area, row_baseline).c1, Point::new(0., 100.));
+    }
+
+    #[test]
+    fn row_extent_fits_ascent_and_descent() {
+        let baseline = LayoutParams::new().align(Align::Start, Align::Baseline);
+        let tall_top = cell(10., 20., Some(18.), baseline.clone());
+        let deep = cell(10., 20., Some(4.), baseline);
+        assert_eq!(row_extent(&[&tall_top, &deep]), (34., 18.));
+    }
+}
@@ -1,6 +1,23 @@
 pub mod interface;
 pub mod render_gl;
 pub mod render_text;
+pub mod stroke;
+pub mod path;
+pub mod theme;
+pub mod overlay;
+pub mod scroll;
+pub mod layout;
+pub mod events;
+pub mod choice;
+pub mod number;
+pub mod form;
+pub mod suggest;
+pub mod tags;
+pub mod symbols;
+pub mod tooltip;
+pub mod dialog;
+pub mod notify;
+pub mod timer;
 #[macro_use]
 pub mod primitives;
 pub mod widgets;
@@ -3,13 +3,29 @@ extern crate gl;
 extern crate nalgebra_glm;
 extern crate bitflags;
 
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::{Keycode};
-use std::time::{SystemTime, Duration};
 
 pub mod interface;
 pub mod render_gl;
 pub mod render_text;
+pub mod stroke;
+pub mod path;
+pub mod theme;
+pub mod overlay;
+pub mod scroll;
+pub mod layout;
+pub mod events;
+pub mod choice;
+pub mod number;
+pub mod form;
+pub mod suggest;
+pub mod tags;
+pub mod symbols;
+pub mod tooltip;
+pub mod dialog;
+pub mod notify;
+pub mod timer;
 #[macro_use]
 pub mod primitives;
 #[macro_use]
@@ -23,57 +39,69 @@ use primitives::{*};
 fn main() {
     let sdl = sdl2::init().unwrap();
     let video_subsystem = sdl.video().unwrap();
+    let gl_attr = video_subsystem.gl_attr();
+    gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
+    gl_attr.set_context_version(3, 3);
+    gl_attr.set_context_flags().forward_compatible().set();
+    gl_attr.set_stencil_size(8);
     const VIEWPORT: Point = Point{x:640., y:480.};
     let window = video_subsystem
         .window("Markdown App", VIEWPORT.x as u32, VIEWPORT.y as u32)
         .opengl()
+        .allow_highdpi()
         .resizable()
         .build()
         .unwrap();
 
-    let gl_attr = video_subsystem.gl_attr();
     let _gl_context = window.gl_create_context().unwrap();
-    gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
-    gl_attr.set_context_version(4,2);
     let _gl = gl::load_with(|s| video_subsystem.gl_get_proc_address(s) as *const std::os::raw::c_void);
 
-    let bg_color = rgb_to_f32(128, 128, 128);
     unsafe {
-        gl::Viewport(0, 0, VIEWPORT.x as i32, VIEWPORT.y as i32);
-        gl::ClearColor(bg_color[0], bg_color[1], bg_color[2], bg_color[3]);
         gl::Enable(gl::BLEND);
         gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
     }
 
-    let mut app_state = AppState::new(&VIEWPORT, window);
+    let mut app_state = AppState::new(window);
 
     let mut event_pump = sdl.event_pump().unwrap();
-    let mut timer = SystemTime::now();
 
-    const FPS: u64 = 120;
     /*unsafe { 
         gl::Clear(gl::COLOR_BUFFER_BIT); 
     }*/
     'main: loop {
-        for event in event_pump.poll_iter() {
+        app_state.tick();
+        app_state.render();
+        //sleeps until the next event or until something scheduled is due, rounding
+        //up so it doesn't wake just before and spin
+        let first = match app_state.timeout() {
+            Some(t) => event_pump.wait_event_timeout(((t.as_micros() + 999) / 1000).min(u32::MAX as u128) as u32),
+            None => Some(event_pump.wait_event())
+        };
+        let events: Vec<Event> = first.into_iter().chain(event_pump.poll_iter()).collect();
+        for event in events {
             let kmod = sdl.keyboard().mod_state();
             match event {
-                Event::Quit {..} | 
-                Event::KeyDown { keycode: Some(Keycode::Escape), ..} => break 'main,
+                Event::Quit {..} => break 'main,
+                //escape closes popups first
+                Event::KeyDown { keycode: Some(Keycode::Escape), ..} if !app_state.overlay.has_active() => break 'main,
                 ev @ Event::MouseMotion{..} | 
                 ev @ Event::MouseButtonDown{..} | 
-                ev @ Event::MouseButtonUp{..} => { 
+                ev @ Event::MouseButtonUp{..} | 
+                ev @ Event::MouseWheel{..} => { 
                     app_state.handle_mouse_event(&ev, &kmod);
                 }
+                Event::KeyDown { keycode: Some(Keycode::F2), ..} => {
+                    app_state.toggle_theme();
+                }
                 ev @ Event::KeyDown {..} => {
                     app_state.handle_keyboard_event(&ev);
                 }
+                Event::Window { win_event: WindowEvent::Resized(..), .. } |
+                Event::Window { win_event: WindowEvent::SizeChanged(..), .. } => {
+                    app_state.resize();
+                }
                 _ => {},
             }
         }
-        if timer.elapsed().unwrap() >= Duration::from_millis(1000 / FPS) {
-            app_state.render();
-            timer = SystemTime::now();
-        }
     }
 }
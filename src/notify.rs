@@ -0,0 +1,222 @@
+use std::time::{Duration, Instant};
+use nalgebra_glm as glm;
+use crate::primitives::{DrawCtx, Point, Rect, Radians, Radii, Paint};
+use crate::render_text::{TextParams};
+use crate::theme::{ThemeColor};
+use crate::tooltip::{wrap};
+use crate::timer::{FRAME};
+
+//toasts start fading out this long before they disappear
+const FADE: Duration = Duration::from_millis(500);
+//toast text wraps at this many chars
+const TOAST_CHARS: usize = 36;
+//most toasts shown at once, older ones are dropped first
+const MAX_TOASTS: usize = 5;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error
+}
+
+impl Severity {
+    fn color(&self) -> ThemeColor {
+        match self {
+            Severity::Info => ThemeColor::Focus,
+            Severity::Success => ThemeColor::Success,
+            Severity::Warning => ThemeColor::Warning,
+            Severity::Error => ThemeColor::Error
+        }
+    }
+    //errors stay up longer so there is time to read them
+    fn timeout(&self) -> Duration {
+        match self {
+            Severity::Info | Severity::Success => Duration::from_secs(3),
+            Severity::Warning => Duration::from_secs(5),
+            Severity::Error => Duration::from_secs(8)
+        }
+    }
+}
+
+fn with_alpha(c: glm::Vec4, alpha: f32) -> glm::Vec4 {
+    glm::vec4(c[0], c[1], c[2], c[3] * alpha)
+}
+
+fn text_color(color: glm::Vec4) -> TextParams {
+    TextParams { color: Some(color), ..TextParams::new() }
+}
+
+struct Toast {
+    severity: Severity,
+    lines: Vec<String>,
+    since: Instant
+}
+
+impl Toast {
+    fn remaining(&self) -> Duration {
+        self.severity.timeout().checked_sub(self.since.elapsed()).unwrap_or(Duration::from_secs(0))
+    }
+    fn alpha(&self) -> f32 {
+        (self.remaining().as_secs_f32() / FADE.as_secs_f32()).min(1.)
+    }
+}
+
+//notifications that stack up from the bottom right corner and fade out on their own
+pub struct Toasts {
+    toasts: Vec<Toast>
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Toasts { toasts: Vec::new() }
+    }
+    pub fn push(&mut self, severity: Severity, text: &str, ctx: &DrawCtx) {
+        let lines = wrap(text, ctx.render_text.measure(&"a".repeat(TOAST_CHARS), 1.).x, ctx);
+        self.add(Toast { severity, lines, since: Instant::now() });
+    }
+    fn add(&mut self, toast: Toast) {
+        self.toasts.push(toast);
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+    //drops expired toasts, true if anything needs redrawing
+    pub fn tick(&mut self) -> bool {
+        let n = self.toasts.len();
+        self.toasts.retain(|t| t.remaining() > Duration::from_secs(0));
+        n != self.toasts.len() || self.toasts.iter().any(|t| t.remaining() < FADE)
+    }
+    //every frame while one is fading, otherwise when the next one starts to fade
+    pub fn next_due(&self) -> Option<Instant> {
+        let now = Instant::now();
+        self.toasts.iter().map(|t| match t.remaining() {
+            r if r <= FADE => now + FRAME,
+            r => now + (r - FADE)
+        }).min()
+    }
+    //newest at the bottom, bottom_right is the corner of the area they stack in
+    pub fn draw(&self, bottom_right: &Point, ctx: &DrawCtx) {
+        let theme = &ctx.theme;
+        let (pad, h) = (theme.padding, ctx.render_text.line_height(1.));
+        let stripe = 4.;
+        let mut bottom = bottom_right.y;
+        for t in self.toasts.iter().rev() {
+            let alpha = t.alpha();
+            let width = t.lines.iter().fold(0f32, |w, l| w.max(ctx.render_text.measure(l, 1.).x));
+            let size = Point::new(width + stripe, h * t.lines.len() as f32) + pad * 2.;
+            let c1 = Point::new(bottom_right.x - size.x, bottom - size.y);
+            let r = Rect::new(c1, c1 + size);
+            let radii = Radii::all(theme.radius);
+            ctx.draw_shadow(r.clone(), radii, &theme.shadow(2., 4.));
+            ctx.draw_round_rect(r.clone(), radii, Paint::Solid(with_alpha(theme.palette.widget_bg, alpha)), None);
+            let bar = Rect::new(r.c1, Point::new(r.c1.x + stripe, r.c2.y));
+            ctx.draw_round_rect(bar, radii, Paint::Solid(with_alpha(theme.color(t.severity.color()), alpha)), None);
+            for (i, line) in t.lines.iter().enumerate() {
+                let pos = c1 + pad + Point::new(stripe, i as f32 * h);
+                ctx.draw_text(line, Rect::new(pos, Point::new(r.c2.x - pad.x, pos.y + h)), &text_color(with_alpha(theme.palette.text, alpha)));
+            }
+            bottom = c1.y - theme.spacing.y;
+        }
+    }
+}
+
+//a strip along the bottom of the window showing the latest notification
+pub struct StatusBar {
+    severity: Severity,
+    text: String
+}
+
+#[allow(dead_code)]
+impl StatusBar {
+    pub fn new() -> Self {
+        StatusBar { severity: Severity::Info, text: String::from("Ready") }
+    }
+    pub fn set(&mut self, severity: Severity, text: &str) {
+        self.severity = severity;
+        self.text = text.to_string();
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub fn height(ctx: &DrawCtx) -> f32 {
+        ctx.render_text.line_height(1.) + ctx.theme.padding.y * 2.
+    }
+    //info is drawn in the normal text color, the rest in their severity color
+    pub fn draw(&self, ctx: &DrawCtx) {
+        let theme = &ctx.theme;
+        let c1 = Point::new(0., ctx.viewport.y - StatusBar::height(ctx));
+        ctx.draw_rect(Rect::new(c1, ctx.viewport), theme.palette.widget_bg, true, Radians(0.));
+        ctx.draw_line(c1, Point::new(ctx.viewport.x, c1.y), theme.palette.border, theme.border_width);
+        let color = match self.severity {
+            Severity::Info => theme.palette.text,
+            s => theme.color(s.color())
+        };
+        ctx.draw_text(&self.text, Rect::new(c1 + theme.padding, ctx.viewport), &text_color(color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toast(severity: Severity, text: &str) -> Toast {
+        Toast { severity, lines: vec![text.to_string()], since: Instant::now() }
+    }
+
+    //as if the toast had been up for this long
+    fn aged(mut t: Toast, age: Duration) -> Toast {
+        t.since -= age;
+        t
+    }
+
+    #[test]
+    fn alpha_fades_at_the_end() {
+        let timeout = Severity::Info.timeout();
+        assert_eq!(toast(Severity::Info, "a").alpha(), 1.);
+        let alpha = aged(toast(Severity::Info, "a"), timeout - FADE / 2).alpha();
+        assert!(alpha > 0.4 && alpha <= 0.5);
+        assert_eq!(aged(toast(Severity::Info, "a"), timeout).alpha(), 0.);
+    }
+
+    #[test]
+    fn tick_drops_expired_toasts() {
+        let mut toasts = Toasts::new();
+        assert!(!toasts.tick());
+        toasts.add(toast(Severity::Info, "fresh"));
+        assert!(!toasts.tick());
+        toasts.add(aged(toast(Severity::Error, "fading"), Severity::Error.timeout() - FADE / 2));
+        assert!(toasts.tick());
+        assert_eq!(toasts.toasts.len(), 2);
+        toasts.add(aged(toast(Severity::Info, "gone"), Severity::Info.timeout()));
+        assert!(toasts.tick());
+        let left: Vec<&str> = toasts.toasts.iter().map(|t| t.lines[0].as_str()).collect();
+        assert_eq!(left, vec!["fresh", "fading"]);
+    }
+
+    #[test]
+    fn next_due_is_the_next_fade() {
+        let mut toasts = Toasts::new();
+        assert_eq!(toasts.next_due(), None);
+        let before = Instant::now();
+        toasts.add(toast(Severity::Warning, "a"));
+        let due = toasts.next_due().unwrap();
+        let fade_at = Severity::Warning.timeout() - FADE;
+        assert!(due <= Instant::now() + fade_at && due + FRAME >= before + fade_at);
+        //while one is fading every frame is drawn
+        toasts.add(aged(toast(Severity::Info, "b"), Severity::Info.timeout() - FADE / 2));
+        assert!(toasts.next_due().unwrap() <= Instant::now() + FRAME);
+    }
+
+    #[test]
+    fn oldest_toasts_are_dropped() {
+        let mut toasts = Toasts::new();
+        for i in 0..MAX_TOASTS + 2 {
+            toasts.add(toast(Severity::Info, &i.to_string()));
+        }
+        assert_eq!(toasts.toasts.len(), MAX_TOASTS);
+        assert_eq!(toasts.toasts[0].lines[0], "2");
+        assert_eq!(toasts.toasts[MAX_TOASTS - 1].lines[0], (MAX_TOASTS + 1).to_string());
+    }
+}
@@ -0,0 +1,354 @@
+use std::time::{SystemTime};
+use sdl2::mouse::SystemCursor;
+use sdl2::keyboard::Keycode;
+use crate::primitives::{DrawCtx, Point, Rect, RotateRect, Radians, Radii, Border, Paint, DrawRoundRect, InBounds};
+use crate::textedit::{TextEdit, Blink, get_char_from_keycode};
+use crate::interface::{EventCtx};
+use crate::widgets::{Widget, WidgetResponse, WidgetStatus, Message, just_status, MDDoc};
+use crate::events::{MouseEvent};
+
+//space between the text field and the step buttons
+const BUTTON_GAP: f32 = 2.;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum StepButton {
+    Down,
+    Up
+}
+
+//a text field that only takes numbers, with - and + buttons next to it.
+//With focus the up/down keys and the mouse wheel step the value
+pub struct NumberInput {
+    edit: TextEdit,
+    select_time: Option<SystemTime>,
+    blink: Blink,
+    min: f64,
+    max: f64,
+    step: f64,
+    decimals: usize,
+    //size of the text field, the buttons are squares as tall as the field
+    field: Point,
+    natural_field: Point,
+    hovered: Option<StepButton>
+}
+
+#[allow(dead_code)]
+impl NumberInput {
+    pub fn new(value: Option<f64>, num_chars: usize, ctx: &DrawCtx) -> Self {
+        let field = ctx.render_text.measure(&"0".repeat(num_chars.max(1)), 1.);
+        NumberInput::with_field(value, field)
+    }
+    fn with_field(value: Option<f64>, field: Point) -> Self {
+        let mut w = NumberInput { edit: TextEdit::new("", field), select_time: None, blink: Blink::new(), min: std::f64::MIN, max: std::f64::MAX,
+            step: 1., decimals: 0, field, natural_field: field, hovered: None };
+        if let Some(v) = value {
+            w.set_value(v);
+        }
+        w
+    }
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = min;
+        self.max = max;
+        self.commit();
+        self
+    }
+    //steps that aren't a positive number are ignored
+    pub fn step(mut self, step: f64) -> Self {
+        if step.is_finite() && step > 0. {
+            self.step = step;
+        }
+        self
+    }
+    //digits after the decimal point, 0 for whole numbers
+    pub fn decimals(mut self, decimals: usize) -> Self {
+        self.decimals = decimals;
+        self.commit();
+        self
+    }
+    //None while the field is empty or holds something like a lone `-`
+    pub fn value(&self) -> Option<f64> {
+        self.edit.text().trim().parse::<f64>().ok().filter(|v| v.is_finite())
+    }
+    pub fn set_value(&mut self, v: f64) {
+        let v = v.max(self.min).min(self.max);
+        let text = format!("{:.*}", self.decimals, v);
+        self.edit.set_text(&text);
+    }
+    //clamps and reformats whatever was typed
+    fn commit(&mut self) {
+        match self.value() {
+            Some(v) => self.set_value(v),
+            None => self.edit.set_text("")
+        }
+    }
+    fn step_by(&mut self, steps: f64) -> Option<WidgetResponse> {
+        //values land on the grid the text shows, a step smaller than a digit still moves one digit
+        let unit = 10f64.powi(-(self.decimals as i32));
+        let start = (self.value().unwrap_or(0f64.max(self.min).min(self.max)) / unit).round() * unit;
+        let mut next = ((start + steps * self.step) / unit).round() * unit;
+        if (next - start).abs() < unit / 2. {
+            next = start + steps.signum() * unit;
+        }
+        let before = self.edit.text();
+        self.set_value(next);
+        Some(self.response(&before))
+    }
+    fn response(&self, before: &str) -> WidgetResponse {
+        let resp = just_status(WidgetStatus::REDRAW);
+        if self.edit.text() != before { resp.msg(Message::Changed) } else { resp }
+    }
+    fn accepts(&self, ch: char) -> bool {
+        let text = self.edit.text();
+        match ch {
+            '0'..='9' => true,
+            '.' => self.decimals > 0 && !text.contains('.'),
+            '-' => self.min < 0. && self.edit.cursor_pos() == 0 && !text.starts_with('-'),
+            _ => false
+        }
+    }
+    fn button_rect(&self, b: StepButton) -> Rect {
+        let h = self.field.y;
+        let x = self.field.x + BUTTON_GAP + if b == StepButton::Up { h } else { 0. };
+        Rect::new(Point::new(x, 0.), Point::new(x + h, h))
+    }
+    fn button_at(&self, pt: &Point) -> Option<StepButton> {
+        [StepButton::Down, StepButton::Up].iter().find(|b| self.button_rect(**b).in_bounds(pt, &Point::origin())).copied()
+    }
+    fn field_rect(&self) -> RotateRect {
+        RotateRect::from_rect(Rect::new(Point::origin(), self.field), Radians(0.))
+    }
+    fn set_field(&mut self, field: Point) {
+        self.field = field;
+        self.edit.set_size(field);
+    }
+    fn draw_button(&self, b: StepButton, off: &Point, ctx: &DrawCtx) {
+        let theme = &ctx.theme;
+        let r = self.button_rect(b);
+        let r = Rect::new(*off + r.c1, *off + r.c2);
+        let fill = if self.hovered == Some(b) { theme.palette.hover } else { theme.palette.widget_bg };
+        let border = Border::new(Point::new(theme.border_width, theme.border_width), theme.palette.border);
+        ctx.draw_round_rect(r.clone(), Radii::all(theme.radius), Paint::Solid(fill), Some(&border));
+        let (c, arm) = ((r.c1 + r.c2) / 2., r.width() * 0.25);
+        let color = theme.palette.text;
+        ctx.draw_line(c - Point::new(arm, 0.), c + Point::new(arm, 0.), color, 2.);
+        if b == StepButton::Up {
+            ctx.draw_line(c - Point::new(0., arm), c + Point::new(0., arm), color, 2.);
+        }
+    }
+}
+
+pub fn new_number_input(value: Option<f64>, num_chars: usize, decimals: usize, ctx: &DrawCtx) -> Box<dyn Widget> {
+    Box::new(NumberInput::new(value, num_chars, ctx).decimals(decimals))
+}
+
+impl Widget for NumberInput {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        Point::new(self.field.x + BUTTON_GAP + self.field.y * 2., self.field.y)
+    }
+    fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
+        let field = self.natural_field;
+        self.set_field(field);
+        self.measure(ctx)
+    }
+    //extra width goes to the text field
+    fn arrange(&mut self, size: &Point, _: &DrawCtx) {
+        let width = size.x - BUTTON_GAP - self.field.y * 2.;
+        let field = Point::new(width.max(self.natural_field.x), self.natural_field.y);
+        self.set_field(field);
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        Some(ctx.render_text.baseline(1.))
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        let theme = &ctx.theme;
+        let rect = RotateRect { offset: *off, ..self.field_rect() };
+        let (border_width, border_color) = match self.select_time {
+            Some(_) => (theme.focus_width, theme.palette.focus),
+            None => (theme.border_width, theme.palette.border)
+        };
+        DrawRoundRect::new(rect.clone(), Paint::Solid(theme.palette.widget_bg))
+            .border(border_width, border_color)
+            .draw(ctx);
+        ctx.push_clip_rotated(&rect);
+        self.edit.draw(&rect, self.select_time.is_some() && self.blink.visible(), ctx);
+        ctx.pop_clip();
+        self.draw_button(StepButton::Down, off, ctx);
+        self.draw_button(StepButton::Up, off, ctx);
+    }
+    //the press takes focus, the step buttons step on the click after the release
+    fn mouse_event(&mut self, ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        match *ev {
+            MouseEvent::Move => {
+                let hovered = self.button_at(pt);
+                *ctx.cursor = if hovered.is_some() { SystemCursor::Hand } else { SystemCursor::IBeam };
+                if hovered == self.hovered {
+                    return Some(just_status(WidgetStatus::FINE))
+                }
+                self.hovered = hovered;
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            MouseEvent::Press { .. } => {
+                self.select_time = Some(SystemTime::now());
+                if self.button_at(pt).is_none() {
+                    let len = self.edit.text().chars().count();
+                    let pos = self.edit.hover_text(pt, &self.field_rect(), ctx.draw_ctx).unwrap_or(len);
+                    self.edit.set_cursor_pos(pos);
+                }
+                Some(just_status(WidgetStatus::REDRAW).msg(self.blink.start()))
+            }
+            MouseEvent::Leave => {
+                self.hovered = None;
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            MouseEvent::Click { .. } => match self.button_at(pt)? {
+                StepButton::Down => self.step_by(-1.),
+                StepButton::Up => self.step_by(1.)
+            },
+            _ => None
+        }
+    }
+    //only steps with focus so the wheel still scrolls the form past it
+    fn scroll(&mut self, _: &Point, delta: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+        if self.select_time.is_none() || delta.y == 0. {
+            return None
+        }
+        self.step_by(-delta.y.signum() as f64)
+    }
+    fn key_down(&mut self, kc: &Keycode, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        self.select_time?;
+        self.blink.reset();
+        let before = self.edit.text();
+        match *kc {
+            Keycode::Up => return self.step_by(1.),
+            Keycode::Down => return self.step_by(-1.),
+            Keycode::Return => self.commit(),
+            Keycode::Left | Keycode::Right | Keycode::Backspace => {
+                self.edit.key_down(kc, &ctx.draw_ctx.render_text);
+            }
+            _ => match get_char_from_keycode(*kc) {
+                Some(ch) if self.accepts(ch) => {
+                    self.edit.key_down(kc, &ctx.draw_ctx.render_text);
+                }
+                _ => return Some(just_status(WidgetStatus::FINE))
+            }
+        }
+        Some(self.response(&before))
+    }
+    fn deselect(&mut self) -> Option<WidgetResponse> {
+        self.select_time?;
+        self.select_time = None;
+        self.blink.stop();
+        let before = self.edit.text();
+        self.commit();
+        Some(self.response(&before))
+    }
+    fn serialize(&self, buf: &mut MDDoc) {
+        if let Some(v) = self.value() {
+            let v = v.max(self.min).min(self.max);
+            buf.body.extend_from_slice(format!("{:.*}", self.decimals, v).as_bytes())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(value: Option<f64>) -> NumberInput {
+        NumberInput::with_field(value, Point::new(100., 20.))
+    }
+
+    fn typed(mut w: NumberInput, text: &str) -> NumberInput {
+        w.edit.set_text(text);
+        w
+    }
+
+    fn changed(resp: Option<WidgetResponse>) -> bool {
+        matches!(resp.unwrap().msgs.as_slice(), [Message::Changed])
+    }
+
+    #[test]
+    fn accepts_digits_one_point_and_a_leading_minus() {
+        let w = input(None);
+        assert!(w.accepts('7'));
+        assert!(!w.accepts('.'));
+        assert!(w.accepts('-'));
+        assert!(!w.accepts('e'));
+        assert!(!input(None).range(0., 10.).accepts('-'));
+        let mut w = typed(input(None).decimals(2).range(-10., 10.), "1.5");
+        assert!(!w.accepts('.'));
+        assert!(!w.accepts('-'));
+        w.edit.set_cursor_pos(0);
+        assert!(w.accepts('-'));
+        let mut w = typed(w, "-1");
+        w.edit.set_cursor_pos(0);
+        assert!(!w.accepts('-'));
+    }
+
+    #[test]
+    fn commit_clamps_and_reformats() {
+        let mut w = typed(input(None).range(0., 10.), "15");
+        w.commit();
+        assert_eq!(w.edit.text(), "10");
+        w.set_value(-3.);
+        assert_eq!(w.edit.text(), "0");
+        let mut w = typed(input(None).decimals(2), "1.5");
+        w.commit();
+        assert_eq!(w.edit.text(), "1.50");
+        assert_eq!(w.value(), Some(1.5));
+        let mut w = typed(w, "-");
+        assert_eq!(w.value(), None);
+        w.commit();
+        assert_eq!(w.edit.text(), "");
+    }
+
+    #[test]
+    fn range_clamps_the_current_value() {
+        let w = input(Some(50.)).range(0., 10.);
+        assert_eq!(w.value(), Some(10.));
+    }
+
+    #[test]
+    fn step_moves_at_least_one_digit() {
+        let mut w = input(Some(2.)).step(0.5);
+        assert!(changed(w.step_by(1.)));
+        assert_eq!(w.edit.text(), "3");
+        w.step_by(-1.);
+        w.step_by(-1.);
+        assert_eq!(w.edit.text(), "1");
+        let mut w = input(Some(1.)).decimals(1).step(0.01);
+        w.step_by(1.);
+        assert_eq!(w.edit.text(), "1.1");
+    }
+
+    #[test]
+    fn step_snaps_to_the_grid() {
+        let mut w = typed(input(None).decimals(2).step(0.1), "0.104");
+        w.step_by(1.);
+        assert_eq!(w.edit.text(), "0.20");
+        let mut w = input(Some(0.)).decimals(1).step(0.1);
+        for _ in 0..3 {
+            w.step_by(1.);
+        }
+        assert_eq!(w.edit.text(), "0.3");
+    }
+
+    #[test]
+    fn step_stops_at_the_range() {
+        let mut w = input(Some(9.)).range(0., 10.).step(5.);
+        assert!(changed(w.step_by(1.)));
+        assert_eq!(w.edit.text(), "10");
+        assert!(!changed(w.step_by(1.)));
+        //an empty field steps from 0, or the nearest end of the range
+        let mut w = input(None).range(5., 10.);
+        w.step_by(1.);
+        assert_eq!(w.edit.text(), "6");
+    }
+
+    #[test]
+    fn bad_steps_are_ignored() {
+        for step in &[0., -1., f64::NAN, f64::INFINITY] {
+            assert_eq!(input(None).step(*step).step, 1.);
+        }
+    }
+}
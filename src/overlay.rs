@@ -0,0 +1,137 @@
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::primitives::{DrawCtx, Point, Rect, Radians, InBounds};
+use nalgebra_glm as glm;
+use crate::widgets::{Widget, WidgetResponse, combine_response_opt};
+use crate::interface::{EventCtx};
+
+static NEXT_POPUP_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PopupId(usize);
+
+impl PopupId {
+    pub fn new() -> Self {
+        PopupId(NEXT_POPUP_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+//the owner of a popup sets the anchor to its window position every time it draws,
+//so popups follow their owner without taking part in its layout
+pub type Anchor = Rc<Cell<Point>>;
+
+pub fn new_anchor() -> Anchor {
+    Rc::new(Cell::new(Point::origin()))
+}
+
+#[derive(Clone)]
+pub struct Popup {
+    pub id: PopupId,
+    pub widget: Rc<RefCell<dyn Widget>>,
+    pub anchor: Anchor,
+    //position of the popup relative to the anchor
+    pub offset: Point,
+    //passive popups are only drawn, the mouse goes through them
+    pub passive: bool,
+    //modal popups are centered over a dimmed window and block the mouse and keys
+    //from reaching anything opened before them
+    pub modal: bool,
+}
+
+impl Popup {
+    pub fn new(id: PopupId, widget: Rc<RefCell<dyn Widget>>, anchor: Anchor, offset: Point) -> Self {
+        Popup { id, widget, anchor, offset, passive: false, modal: false }
+    }
+    pub fn modal(mut self) -> Self {
+        self.modal = true;
+        self
+    }
+    pub fn passive(mut self) -> Self {
+        self.passive = true;
+        self
+    }
+    pub fn position(&self) -> Point {
+        self.anchor.get() + self.offset
+    }
+    pub fn rect(&self, ctx: &DrawCtx) -> Rect {
+        let pos = self.position();
+        Rect::new(pos, pos + self.widget.borrow().measure(ctx))
+    }
+}
+
+//popups drawn on top of the interface in the order they were opened,
+//the most recent popup is hit tested first
+pub struct Overlay {
+    popups: Vec<Popup>
+}
+
+#[allow(dead_code)]
+impl Overlay {
+    pub fn new() -> Self {
+        Overlay { popups: Vec::new() }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.popups.is_empty()
+    }
+    //true if any popup besides passive ones like tooltips is open
+    pub fn has_active(&self) -> bool {
+        self.popups.iter().any(|p| !p.passive)
+    }
+    fn modal_idx(&self) -> Option<usize> {
+        self.popups.iter().rposition(|p| p.modal)
+    }
+    //the topmost modal popup, which gets the keys while it is open
+    pub fn modal(&self) -> Option<PopupId> {
+        self.modal_idx().map(|i| self.popups[i].id)
+    }
+    pub fn is_open(&self, id: PopupId) -> bool {
+        self.popups.iter().any(|p| p.id == id)
+    }
+    pub fn open(&mut self, popup: Popup) {
+        self.close(popup.id);
+        self.popups.push(popup);
+    }
+    pub fn close(&mut self, id: PopupId) -> Option<WidgetResponse> {
+        let idx = self.popups.iter().position(|p| p.id == id)?;
+        let popup = self.popups.remove(idx);
+        let resp = popup.widget.borrow_mut().deselect();
+        resp
+    }
+    pub fn close_all(&mut self) -> Option<WidgetResponse> {
+        self.popups.drain(..).rev().fold(None, |resp, p| {
+            let r = p.widget.borrow_mut().deselect();
+            combine_response_opt(resp, r)
+        })
+    }
+    pub fn draw(&self, ctx: &DrawCtx) {
+        let modal = self.modal_idx();
+        for (i, p) in self.popups.iter().enumerate() {
+            if p.modal {
+                let size = p.widget.borrow().measure(ctx);
+                p.anchor.set((ctx.viewport - size) / 2. - p.offset);
+            }
+            if Some(i) == modal {
+                let shadow = ctx.theme.palette.shadow;
+                ctx.draw_rect(Rect::new(Point::origin(), ctx.viewport), glm::vec4(shadow[0], shadow[1], shadow[2], 0.4), true, Radians(0.));
+            }
+            p.widget.borrow().draw(&p.position(), ctx);
+        }
+    }
+    pub fn get(&self, id: PopupId) -> Option<&Popup> {
+        self.popups.iter().find(|p| p.id == id)
+    }
+    //topmost popup under the point, popups below a modal one can't be hit
+    pub fn popup_at(&self, pt: &Point, ctx: &DrawCtx) -> Option<PopupId> {
+        let start = self.modal_idx().unwrap_or(0);
+        self.popups[start..].iter().rev().find(|p| !p.passive && p.rect(ctx).in_bounds(pt, &ctx.viewport)).map(|p| p.id)
+    }
+    //Some if the point is over a popup, in which case the interface should not see the event
+    pub fn scroll(&mut self, pt: &Point, delta: &Point, ctx: &mut EventCtx) -> Option<Option<WidgetResponse>> {
+        let id = self.popup_at(pt, ctx.draw_ctx)?;
+        let p = self.get(id)?;
+        let local = *pt - p.position();
+        let resp = p.widget.borrow_mut().scroll(&local, delta, ctx);
+        Some(resp)
+    }
+}
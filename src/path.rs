@@ -0,0 +1,367 @@
+extern crate gl;
+extern crate nalgebra_glm;
+
+use gl::types::GLuint;
+use nalgebra_glm as glm;
+use std::f32::consts::PI;
+use crate::primitives::{Point, PrimType, DrawCtx, Radians};
+use crate::render_gl::{SendUniform, SendUniforms};
+use crate::stroke::{StrokeStyle, stroke_verts, draw_stroke, arc_segments};
+use md_ui_derive::SendUniforms;
+
+//max distance in pixels between a flattened curve and the true curve
+const CURVE_TOLERANCE: f32 = 0.25;
+const MAX_CURVE_SEGMENTS: usize = 100;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum PathCmd {
+    MoveTo(Point),
+    LineTo(Point),
+    QuadTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    Close
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Path {
+    cmds: Vec<PathCmd>,
+    start: Option<Point>,
+    current: Option<Point>
+}
+
+#[allow(dead_code)]
+impl Path {
+    pub fn new() -> Self {
+        Path { cmds: Vec::new(), start: None, current: None }
+    }
+    pub fn move_to(mut self, p: Point) -> Self {
+        self.cmds.push(PathCmd::MoveTo(p));
+        self.start = Some(p);
+        self.current = Some(p);
+        self
+    }
+    pub fn line_to(mut self, p: Point) -> Self {
+        if self.current.is_none() {
+            return self.move_to(p);
+        }
+        self.cmds.push(PathCmd::LineTo(p));
+        self.current = Some(p);
+        self
+    }
+    pub fn quad_to(mut self, c: Point, p: Point) -> Self {
+        if self.current.is_none() {
+            self = self.move_to(c);
+        }
+        self.cmds.push(PathCmd::QuadTo(c, p));
+        self.current = Some(p);
+        self
+    }
+    pub fn cubic_to(mut self, c1: Point, c2: Point, p: Point) -> Self {
+        if self.current.is_none() {
+            self = self.move_to(c1);
+        }
+        self.cmds.push(PathCmd::CubicTo(c1, c2, p));
+        self.current = Some(p);
+        self
+    }
+    //angles are measured clockwise on screen from the positive x axis
+    pub fn arc(mut self, center: Point, radius: f32, start: Radians, end: Radians) -> Self {
+        let sweep = end.0 - start.0;
+        if !radius.is_finite() || !sweep.is_finite() {
+            return self;
+        }
+        let n = arc_segments(radius, sweep);
+        let at = |a: f32| center + Point::new(a.cos(), a.sin()) * radius;
+        self = self.line_to(at(start.0));
+        for i in 1..=n {
+            self = self.line_to(at(start.0 + sweep * i as f32 / n as f32));
+        }
+        self
+    }
+    pub fn circle(self, center: Point, radius: f32) -> Self {
+        self.move_to(center + Point::new(radius, 0.))
+            .arc(center, radius, Radians(0.), Radians(2. * PI))
+            .close()
+    }
+    pub fn close(mut self) -> Self {
+        if self.current.is_some() {
+            self.cmds.push(PathCmd::Close);
+            self.current = self.start;
+        }
+        self
+    }
+    //returns each subpath as a polyline and whether it was closed
+    pub fn flatten(&self) -> Vec<(Vec<Point>, bool)> {
+        let mut subpaths = Vec::new();
+        let mut pts: Vec<Point> = Vec::new();
+        for cmd in &self.cmds {
+            match *cmd {
+                PathCmd::MoveTo(p) => {
+                    if pts.len() > 1 {
+                        subpaths.push((std::mem::replace(&mut pts, Vec::new()), false));
+                    }
+                    pts.clear();
+                    pts.push(p);
+                }
+                PathCmd::LineTo(p) => pts.push(p),
+                PathCmd::QuadTo(c, p) => {
+                    let p0 = *pts.last().unwrap();
+                    let n = curve_segments((p0 - c * 2. + p).mag() / 8.);
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1. - t;
+                        pts.push(p0 * (mt * mt) + c * (2. * mt * t) + p * (t * t));
+                    }
+                }
+                PathCmd::CubicTo(c1, c2, p) => {
+                    let p0 = *pts.last().unwrap();
+                    let dd = (p0 - c1 * 2. + c2).mag().max((c1 - c2 * 2. + p).mag());
+                    let n = curve_segments(dd * 0.75);
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1. - t;
+                        pts.push(p0 * (mt * mt * mt) + c1 * (3. * mt * mt * t) + c2 * (3. * mt * t * t) + p * (t * t * t));
+                    }
+                }
+                PathCmd::Close => {
+                    if pts.len() > 0 {
+                        let start = pts[0];
+                        subpaths.push((std::mem::replace(&mut pts, vec![start]), true));
+                    }
+                }
+            }
+        }
+        if pts.len() > 1 {
+            subpaths.push((pts, false));
+        }
+        subpaths
+    }
+}
+
+//a flattened curve deviates from the curve by roughly deviation / n^2
+fn curve_segments(deviation: f32) -> usize {
+    ((deviation / CURVE_TOLERANCE).sqrt().ceil() as usize).max(1).min(MAX_CURVE_SEGMENTS)
+}
+
+struct Edge {
+    top: Point,
+    bottom: Point,
+    winding: i32
+}
+
+impl Edge {
+    fn x_at(&self, y: f32) -> f32 {
+        let t = (y - self.top.y) / (self.bottom.y - self.top.y);
+        self.top.x + (self.bottom.x - self.top.x) * t
+    }
+}
+
+fn intersect_y(e1: &Edge, e2: &Edge) -> Option<f32> {
+    let d1 = e1.bottom - e1.top;
+    let d2 = e2.bottom - e2.top;
+    let denom = d1.cross(&d2);
+    if denom.abs() < 1e-9 {
+        return None;
+    }
+    let diff = e2.top - e1.top;
+    let t = diff.cross(&d2) / denom;
+    let u = diff.cross(&d1) / denom;
+    if t > 0. && t < 1. && u > 0. && u < 1. {
+        Some(e1.top.y + d1.y * t)
+    }
+    else { None }
+}
+
+//splits the filled area into horizontal bands where no edges cross, then
+//emits a trapezoid for each inside span of every band
+pub fn fill_verts(contours: &[Vec<Point>], rule: FillRule) -> Vec<f32> {
+    let mut edges = Vec::new();
+    for c in contours {
+        for i in 0..c.len() {
+            let (a, b) = (c[i], c[(i + 1) % c.len()]);
+            //degenerate arcs and curves can leave NaN points, they would poison the sorts below
+            let finite = |p: Point| p.x.is_finite() && p.y.is_finite();
+            if !finite(a) || !finite(b) || (a.y - b.y).abs() < 1e-6 {
+                continue;
+            }
+            edges.push(if a.y < b.y { Edge { top: a, bottom: b, winding: 1 } }
+                       else { Edge { top: b, bottom: a, winding: -1 } });
+        }
+    }
+    let mut ys: Vec<f32> = edges.iter().map(|e| e.top.y).chain(edges.iter().map(|e| e.bottom.y)).collect();
+    for (i, e1) in edges.iter().enumerate() {
+        for e2 in edges[i+1..].iter() {
+            if e1.top.y < e2.bottom.y && e2.top.y < e1.bottom.y {
+                ys.extend(intersect_y(e1, e2));
+            }
+        }
+    }
+    ys.sort_by(|a, b| a.total_cmp(b));
+    ys.dedup_by(|a, b| (*a - *b).abs() < 1e-4);
+
+    let inside = |w: i32| match rule {
+        FillRule::NonZero => w != 0,
+        FillRule::EvenOdd => w % 2 != 0
+    };
+    let mut verts = Vec::new();
+    for band in ys.windows(2) {
+        let (y0, y1) = (band[0], band[1]);
+        let ym = (y0 + y1) / 2.;
+        let mut active: Vec<(f32, &Edge)> = edges.iter()
+            .filter(|e| e.top.y < ym && e.bottom.y > ym)
+            .map(|e| (e.x_at(ym), e))
+            .collect();
+        active.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut winding = 0;
+        let mut span_start: Option<&Edge> = None;
+        for (_, e) in active {
+            let was_inside = inside(winding);
+            winding += e.winding;
+            match (was_inside, inside(winding)) {
+                (false, true) => span_start = Some(e),
+                (true, false) => {
+                    let s = span_start.take().unwrap();
+                    let (sx0, sx1, ex0, ex1) = (s.x_at(y0), s.x_at(y1), e.x_at(y0), e.x_at(y1));
+                    verts.extend_from_slice(&[sx0, y0, ex0, y0, ex1, y1]);
+                    verts.extend_from_slice(&[sx0, y0, ex1, y1, sx1, y1]);
+                }
+                _ => {}
+            }
+        }
+    }
+    verts
+}
+
+#[derive(SendUniforms)]
+struct PathUniforms {
+    projection: glm::Mat4,
+    color: glm::Vec4
+}
+
+pub fn draw_triangles(verts: &[f32], color: &glm::Vec4, ctx: &DrawCtx) {
+    let prog = &ctx.prog_map[&PrimType::Path];
+    prog.set_used();
+    let vp = ctx.viewport;
+    PathUniforms {
+        projection: glm::ortho(0., vp.x, vp.y, 0., -1., 1.),
+        color: *color
+    }.send_uniforms(prog.id()).unwrap();
+    ctx.path_stream.draw(verts, verts.len() / 2, gl::TRIANGLES);
+}
+
+pub struct DrawPath<'a> {
+    pub path: &'a Path,
+    pub color: glm::Vec4
+}
+
+impl<'a> DrawPath<'a> {
+    pub fn fill(&self, rule: FillRule, ctx: &DrawCtx) {
+        let contours: Vec<Vec<Point>> = self.path.flatten().into_iter().map(|(pts, _)| pts).collect();
+        let verts = fill_verts(&contours, rule);
+        draw_triangles(&verts, &self.color, ctx);
+    }
+    pub fn stroke(&self, style: &StrokeStyle, ctx: &DrawCtx) {
+        let mut verts = Vec::new();
+        for (pts, closed) in self.path.flatten() {
+            verts.extend(stroke_verts(&pts, closed, style));
+        }
+        draw_stroke(&verts, &self.color, style, ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32, size: f32) -> Vec<Point> {
+        vec![Point::new(x, y), Point::new(x + size, y), Point::new(x + size, y + size), Point::new(x, y + size)]
+    }
+
+    //total area of the triangles fill_verts emits
+    fn area(verts: &[f32]) -> f32 {
+        verts.chunks(6).map(|t| ((t[2] - t[0]) * (t[5] - t[1]) - (t[4] - t[0]) * (t[3] - t[1])).abs() / 2.).sum()
+    }
+
+    #[test]
+    fn flatten_splits_subpaths() {
+        let path = Path::new()
+            .move_to(Point::new(0., 0.)).line_to(Point::new(10., 0.)).line_to(Point::new(10., 10.)).close()
+            .move_to(Point::new(20., 0.)).line_to(Point::new(30., 0.));
+        let subpaths = path.flatten();
+        assert_eq!(subpaths.len(), 2);
+        assert_eq!(subpaths[0], (vec![Point::new(0., 0.), Point::new(10., 0.), Point::new(10., 10.)], true));
+        assert_eq!(subpaths[1], (vec![Point::new(20., 0.), Point::new(30., 0.)], false));
+    }
+
+    #[test]
+    fn flatten_curves_end_on_their_end_point() {
+        let path = Path::new().move_to(Point::new(0., 0.))
+            .quad_to(Point::new(50., 100.), Point::new(100., 0.))
+            .cubic_to(Point::new(120., 50.), Point::new(180., -50.), Point::new(200., 0.));
+        let (pts, closed) = &path.flatten()[0];
+        assert!(!closed);
+        assert!(pts.len() > 3);
+        assert_eq!(*pts.last().unwrap(), Point::new(200., 0.));
+        assert!(pts.contains(&Point::new(100., 0.)));
+    }
+
+    #[test]
+    fn arc_with_infinite_radius_is_skipped() {
+        let path = Path::new().circle(Point::new(0., 0.), f32::INFINITY);
+        assert!(path.flatten().iter().all(|(pts, _)| pts.len() <= 1));
+        let path = Path::new().move_to(Point::new(0., 0.)).arc(Point::new(0., 0.), 10., Radians(0.), Radians(f32::INFINITY));
+        assert_eq!(path.cmds.len(), 1);
+    }
+
+    #[test]
+    fn huge_sweep_is_bounded() {
+        let path = Path::new().arc(Point::new(0., 0.), 10., Radians(0.), Radians(1e9));
+        let (pts, _) = &path.flatten()[0];
+        assert!(pts.len() <= 101);
+    }
+
+    #[test]
+    fn fill_square() {
+        let verts = fill_verts(&[square(0., 0., 10.)], FillRule::NonZero);
+        assert!((area(&verts) - 100.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn nested_square_same_direction() {
+        let contours = [square(0., 0., 10.), square(3., 3., 4.)];
+        assert!((area(&fill_verts(&contours, FillRule::NonZero)) - 100.).abs() < 1e-3);
+        assert!((area(&fill_verts(&contours, FillRule::EvenOdd)) - 84.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn nested_square_reversed_is_a_hole() {
+        let mut hole = square(3., 3., 4.);
+        hole.reverse();
+        let contours = [square(0., 0., 10.), hole];
+        assert!((area(&fill_verts(&contours, FillRule::NonZero)) - 84.).abs() < 1e-3);
+        assert!((area(&fill_verts(&contours, FillRule::EvenOdd)) - 84.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn crossing_edges() {
+        //a bow tie, both triangles are filled with either rule
+        let bow = vec![Point::new(0., 0.), Point::new(10., 10.), Point::new(10., 0.), Point::new(0., 10.)];
+        let bow = std::slice::from_ref(&bow);
+        assert!((area(&fill_verts(bow, FillRule::NonZero)) - 50.).abs() < 1e-3);
+        assert!((area(&fill_verts(bow, FillRule::EvenOdd)) - 50.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn non_finite_points_are_skipped() {
+        let mut c = square(0., 0., 10.);
+        c.push(Point::new(f32::NAN, 5.));
+        assert!(fill_verts(&[c], FillRule::NonZero).iter().all(|v| v.is_finite()));
+        assert!(fill_verts(&[vec![Point::new(f32::INFINITY, 0.), Point::new(0., f32::NAN)]], FillRule::EvenOdd).is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use gl::types::{GLuint, GLint, GLfloat, GLenum, GLvoid, GLsizeiptr};
+use gl::types::{GLuint, GLint, GLenum, GLvoid, GLsizeiptr};
 extern crate gl;
 extern crate nalgebra_glm;
 extern crate newtype_derive;
@@ -8,8 +8,11 @@ use std::collections::HashMap;
 use std::ffi::CString;
 use std::f32::{self, consts::PI};
 use PrimType as PT;
-use crate::render_gl::{Shader, Program, SendUniforms, SendUniform};
-use crate::render_text::{RenderText};
+use crate::render_gl::{Shader, Program, SendUniforms, SendUniform, VertexStream};
+use crate::render_text::{RenderText, TextParams};
+use crate::theme::{Theme};
+use crate::stroke::{DrawPolyline, StrokeStyle, LineCap};
+use crate::path::{Path, DrawPath, FillRule, draw_triangles};
 use md_ui_derive::SendUniforms;
 use macro_attr::{macro_attr, macro_attr_impl};
 use newtype_derive::*;
@@ -22,7 +25,7 @@ type ProgMap = HashMap<PrimType, Rc<Program>>;
 
 pub fn prim_map() -> PrimMap {
     let mut m = HashMap::new();
-    for prim in &[PT::Triangle, PT::Circle, PT::Rect, PT::Ring, PT::Line, PT::HexColor] {
+    for prim in &[PT::Rect, PT::HexColor] {
         m.insert(*prim, prim.buffer_data());
     }
     m
@@ -41,8 +44,8 @@ pub fn prog_map() -> ProgMap {
         &CString::new(include_str!("shaders/line.vert")).unwrap()
     ).unwrap();
 
-    let line_geom_shader = Shader::from_geom_source(
-        &CString::new(include_str!("shaders/line.geom")).unwrap()
+    let line_frag_shader = Shader::from_frag_source(
+        &CString::new(include_str!("shaders/line.frag")).unwrap()
     ).unwrap();
 
     let shapecolor_vert = Shader::from_vert_source(
@@ -53,22 +56,33 @@ pub fn prog_map() -> ProgMap {
         &CString::new(include_str!("shaders/shapecolor.frag")).unwrap()
     ).unwrap();
 
+    let path_vert = Shader::from_vert_source(
+        &CString::new(include_str!("shaders/path.vert")).unwrap()
+    ).unwrap();
+
+    let path_frag = Shader::from_frag_source(
+        &CString::new(include_str!("shaders/path.frag")).unwrap()
+    ).unwrap();
+
     let mut shaders = vec![vert_shader, frag_shader];
     let shape_prog = Rc::new(Program::from_shaders(shaders.as_ref()).unwrap());
 
-    shaders[0] = line_shader;
-    shaders.insert(1, line_geom_shader);
+    shaders = vec![line_shader, line_frag_shader];
     let line_prog = Rc::new(Program::from_shaders(shaders.as_ref()).unwrap());
 
     shaders = vec![shapecolor_vert, shapecolor_frag];
     let shapecolor_prog = Rc::new(Program::from_shaders(shaders.as_ref()).unwrap());
 
+    shaders = vec![path_vert, path_frag];
+    let path_prog = Rc::new(Program::from_shaders(shaders.as_ref()).unwrap());
+
     let mut m = HashMap::new();
     for prim in &[PrimType::Triangle, PT::Circle, PT::Rect, PT::Ring] {
         m.insert(*prim, Rc::clone(&shape_prog));
     }
     m.insert(PT::Line, line_prog);
     m.insert(PT::HexColor, shapecolor_prog);
+    m.insert(PT::Path, path_prog);
 
     m
 }
@@ -81,9 +95,13 @@ pub enum PrimType {
     Rect,
     Line,
     HexColor,
+    Path,
 }
 
 const NCIRCLE_VERTS: usize = 30;
+//pixels drawn past a shape's edge so the shape shader can fade it out
+const SHAPE_FRINGE: f32 = 1.5;
+const OUTLINE_WIDTH: f32 = 1.0;
 
 impl PrimType {
     fn verts(&self) -> Vec<f32> {
@@ -115,8 +133,8 @@ impl PrimType {
                     0.0, 1.0
                 ]
             },
-            PT::Line => {
-               vec![0.0, 0.0]
+            PT::Line | PT::Path => { //tessellated per draw, see stroke.rs and path.rs
+               vec![]
             }
             PT::HexColor => {
                 let colors = &[
@@ -134,7 +152,7 @@ impl PrimType {
     fn buffer_data(&self) -> GLuint {
         unsafe {
             match self {
-                //PT::HexColor => { HexColor::buffer_verts(&self.verts().as_slice()) }
+                PT::HexColor => buffer_verts_attribs(&self.verts().as_slice(), &[2, 3]),
                 _ => buffer_verts(&self.verts().as_slice()) 
             }
         }
@@ -142,20 +160,28 @@ impl PrimType {
     fn mode(&self) -> GLenum {
         match self {
             PT::Triangle => gl::TRIANGLES,
-            PT::Rect => gl::QUADS,
+            PT::Rect => gl::TRIANGLE_FAN,
             PT::Circle => gl::TRIANGLE_FAN,
             PT::Ring => gl::LINE_STRIP, 
-            PT::Line => gl::POINTS,
+            PT::Line | PT::Path => gl::TRIANGLES,
             PT::HexColor=> gl::TRIANGLES,
         }
     }
+    fn sdf_shape(&self) -> Option<i32> {
+        match self {
+            PT::Rect => Some(0),
+            PT::Circle | PT::Ring => Some(1),
+            PT::Triangle => Some(2),
+            _ => None
+        }
+    }
     pub fn size(&self) -> usize {
         match self {
             PT::Triangle => 3,
             PT::Rect => 4,
             PT::Circle => NCIRCLE_VERTS + 1, 
             PT::Ring => NCIRCLE_VERTS, 
-            PT::Line => 1,
+            PT::Line | PT::Path => 0,
             PT::HexColor => 3 
         }
     }
@@ -170,7 +196,7 @@ impl PrimType {
             PT::Rect => {
                 p.x >= 0.0 && p.x <= 1.0 && p.y >= 0.0 && p.y <= 1.0 
             }
-            PT::Line => {
+            PT::Line | PT::Path => {
                 false
             }
         }
@@ -178,6 +204,10 @@ impl PrimType {
 }
 
 unsafe fn buffer_verts(verts: &[f32]) -> GLuint {
+    buffer_verts_attribs(verts, &[2])
+}
+
+unsafe fn buffer_verts_attribs(verts: &[f32], attribs: &[GLint]) -> GLuint {
     let mut vao: GLuint = 0;
     let mut vbo: GLuint = 0;
     gl::GenBuffers(1, &mut vbo);
@@ -192,10 +222,14 @@ unsafe fn buffer_verts(verts: &[f32]) -> GLuint {
     gl::GenVertexArrays(1, &mut vao);
     gl::BindVertexArray(vao);
     gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-    gl::EnableVertexAttribArray(0);
-    gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE,
-        (2 * std::mem::size_of::<f32>()) as GLint,
-        std::ptr::null());
+    let stride = attribs.iter().sum::<GLint>() * std::mem::size_of::<f32>() as GLint;
+    let mut offset = 0;
+    for (i, n) in attribs.iter().enumerate() {
+        gl::EnableVertexAttribArray(i as GLuint);
+        gl::VertexAttribPointer(i as GLuint, *n, gl::FLOAT, gl::FALSE, stride,
+            (offset * std::mem::size_of::<f32>()) as *const GLvoid);
+        offset += *n as usize;
+    }
     gl::BindBuffer(gl::ARRAY_BUFFER, 0);
     gl::BindVertexArray(0);
     vao
@@ -243,6 +277,19 @@ impl Point {
     pub fn to_vec4(&self) -> glm::Vec4 {
         glm::vec4(self.x, self.y, 0., 1.)
     }
+    pub fn dot(&self, p2: &Point) -> f32 {
+        self.x * p2.x + self.y * p2.y
+    }
+    pub fn cross(&self, p2: &Point) -> f32 {
+        self.x * p2.y - self.y * p2.x
+    }
+    pub fn perp(&self) -> Point {
+        Point::new(-self.y, self.x)
+    }
+    pub fn normalize(&self) -> Point {
+        let mag = self.mag();
+        if mag > 0. { *self / mag } else { Point::origin() }
+    }
 }
 
 impl std::ops::Add for Point {
@@ -294,6 +341,13 @@ impl std::ops::MulAssign for Point {
     }
 }
 
+impl std::ops::Mul<f32> for Point {
+    type Output = Point;
+    fn mul(self, rhs: f32) -> Self::Output {
+        Point {x: self.x * rhs, y: self.y * rhs}
+    }
+}
+
 impl std::ops::Div<f32> for Point {
     type Output = Point;
     fn div(self, rhs: f32) -> Self {
@@ -327,7 +381,7 @@ pub struct RotateRect {
     pub offset: Point,
     pub size: Point,
     pub rot: Radians,
-    pub trans: TransformCache<(Point, Point, Radians), RectTransform>
+    pub trans: TransformCache<(Point, Point, Radians, Point), RectTransform>
 }
 
 impl RotateRect {
@@ -382,7 +436,7 @@ impl RotateRect {
     }
     pub fn transform(&self, vp: &Point) -> RectTransform {
         self.trans.transform(
-            (self.offset, self.size, self.rot),
+            (self.offset, self.size, self.rot, *vp),
             Box::new(move || RectTransform::new(self, vp)))
     }
     pub fn to_poly(&self) -> DrawPolygon {
@@ -446,6 +500,7 @@ pub struct DrawLine {
     pub p1: Point, 
     pub p2: Point,
     pub line_width: f32,
+    pub cap: LineCap,
     pub color: glm::Vec4
 }
 
@@ -463,19 +518,12 @@ impl DrawLine {
         if self.p1.y > self.p2.y { &mut self.p1.y } else { &mut self.p2.y }
     }
     pub fn draw(&self, ctx: &DrawCtx) {
-        let ptype = &PrimType::Line;
-        ctx.prog_map[ptype].set_used();
-        let trans = LineTransform::new(self, &ctx.viewport);
-        let prog_id = ctx.prog_map[&ptype].id();
-        let vao = ctx.prim_map[&ptype];
-        let line_width = self.line_width; 
-        unsafe {
-            trans.send_uniforms(prog_id).unwrap();
-            self.color.send_uniform(prog_id, "color").unwrap();
-            gl::LineWidth(line_width as GLfloat);
-            gl::BindVertexArray(vao);
-            gl::DrawArrays(gl::POINTS, 0, 1);
-        }
+        DrawPolyline {
+            pts: vec![self.p1, self.p2],
+            closed: false,
+            style: StrokeStyle::new(self.line_width).cap(self.cap),
+            color: self.color
+        }.draw(ctx);
     }
 }
 
@@ -485,6 +533,7 @@ impl Default for DrawLine {
             p1: Point::origin(),
             p2: Point::origin(),
             line_width: 3.,
+            cap: LineCap::Butt,
             color: glm::vec4(0., 0., 0., 1.)
         }
     }
@@ -525,22 +574,198 @@ impl DrawPolygon {
     }
     pub fn draw(&self, ctx: &DrawCtx) {
         let ptype = &self.prim;
+        if let Some(shape) = ptype.sdf_shape() {
+            let mut uniforms = ShapeUniforms::new(shape, &self.rect);
+            if !self.fill || *ptype == PT::Ring {
+                uniforms.stroke = OUTLINE_WIDTH;
+            }
+            draw_sdf(&self.rect, &self.color, &uniforms, ctx);
+            return;
+        }
         ctx.prog_map[ptype].set_used();
         let trans = self.rect.transform(&ctx.viewport);
         let prog_id = ctx.prog_map[ptype].id();
-        let vao = ctx.prim_map[ptype];
-        let poly_mode: GLuint = if self.fill { gl::FILL } else { gl::LINE };
+        trans.send_uniforms(prog_id).unwrap();
+        self.color.send_uniform(prog_id, "color").unwrap();
         unsafe {
-            gl::PolygonMode(gl::FRONT_AND_BACK, poly_mode); 
-            gl::LineWidth(1.);
-            trans.send_uniforms(prog_id).unwrap();
-            self.color.send_uniform(prog_id, "color").unwrap();
-            gl::BindVertexArray(vao);
+            gl::BindVertexArray(ctx.prim_map[ptype]);
             gl::DrawArrays(ptype.mode(), 0, ptype.size() as i32);
         }
     }
 }
 
+#[derive(SendUniforms)]
+struct ShapeUniforms {
+    size: glm::Vec2,
+    fringe: f32,
+    shape: i32,
+    stroke: f32,
+    radii: glm::Vec4,
+    blur: f32,
+    border_width: f32,
+    border_color: glm::Vec4,
+    paint: i32,
+    color2: glm::Vec4,
+    grad_start: glm::Vec2,
+    grad_end: glm::Vec2
+}
+
+impl ShapeUniforms {
+    fn new(shape: i32, r: &RotateRect) -> Self {
+        ShapeUniforms {
+            size: r.size.to_vec2(),
+            fringe: SHAPE_FRINGE,
+            shape,
+            stroke: 0.,
+            radii: glm::vec4(0., 0., 0., 0.),
+            blur: 0.,
+            border_width: 0.,
+            border_color: glm::vec4(0., 0., 0., 0.),
+            paint: 0,
+            color2: glm::vec4(0., 0., 0., 0.),
+            grad_start: glm::vec2(0., 0.),
+            grad_end: glm::vec2(0., 0.)
+        }
+    }
+    //sets the gradient uniforms and returns the base color
+    fn set_paint(&mut self, paint: &Paint) -> glm::Vec4 {
+        let half = Point::new(self.size.x, self.size.y) / 2.;
+        let size = half * 2.;
+        match paint {
+            Paint::Solid(color) => *color,
+            Paint::Linear { start, end, from, to } => {
+                self.paint = 1;
+                self.color2 = *to;
+                self.grad_start = (*start * size - half).to_vec2();
+                self.grad_end = (*end * size - half).to_vec2();
+                *from
+            }
+            Paint::Radial { center, radius, inner, outer } => {
+                self.paint = 2;
+                self.color2 = *outer;
+                let c = *center * size - half;
+                self.grad_start = c.to_vec2();
+                self.grad_end = (c + Point::new(*radius, 0.)).to_vec2();
+                *inner
+            }
+        }
+    }
+}
+
+fn draw_sdf(rect: &RotateRect, color: &glm::Vec4, uniforms: &ShapeUniforms, ctx: &DrawCtx) {
+    let prog = &ctx.prog_map[&PT::Rect];
+    prog.set_used();
+    rect.transform(&ctx.viewport).send_uniforms(prog.id()).unwrap();
+    color.send_uniform(prog.id(), "color").unwrap();
+    uniforms.send_uniforms(prog.id()).unwrap();
+    unsafe {
+        gl::BindVertexArray(ctx.prim_map[&PT::Rect]);
+        gl::DrawArrays(PT::Rect.mode(), 0, PT::Rect.size() as i32);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Radii {
+    pub tl: f32,
+    pub tr: f32,
+    pub br: f32,
+    pub bl: f32
+}
+
+#[allow(dead_code)]
+impl Radii {
+    pub fn new(tl: f32, tr: f32, br: f32, bl: f32) -> Self {
+        Radii { tl, tr, br, bl }
+    }
+    pub fn all(r: f32) -> Self {
+        Radii::new(r, r, r, r)
+    }
+    pub fn top(r: f32) -> Self {
+        Radii::new(r, r, 0., 0.)
+    }
+    pub fn bottom(r: f32) -> Self {
+        Radii::new(0., 0., r, r)
+    }
+    fn to_vec4(&self) -> glm::Vec4 {
+        glm::vec4(self.tl, self.tr, self.br, self.bl)
+    }
+}
+
+//gradient points are in unit coordinates of the filled rect, the radial radius is in pixels
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+    Solid(glm::Vec4),
+    Linear { start: Point, end: Point, from: glm::Vec4, to: glm::Vec4 },
+    Radial { center: Point, radius: f32, inner: glm::Vec4, outer: glm::Vec4 }
+}
+
+#[allow(dead_code)]
+impl Paint {
+    pub fn vertical(top: glm::Vec4, bottom: glm::Vec4) -> Self {
+        Paint::Linear { start: Point::new(0.5, 0.), end: Point::new(0.5, 1.), from: top, to: bottom }
+    }
+    pub fn horizontal(left: glm::Vec4, right: glm::Vec4) -> Self {
+        Paint::Linear { start: Point::new(0., 0.5), end: Point::new(1., 0.5), from: left, to: right }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shadow {
+    pub offset: Point,
+    pub blur: f32,
+    pub spread: f32,
+    pub color: glm::Vec4
+}
+
+impl Shadow {
+    pub fn new(offset: Point, blur: f32, color: glm::Vec4) -> Self {
+        Shadow { offset, blur, spread: 0., color }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawRoundRect {
+    pub rect: RotateRect,
+    pub radii: Radii,
+    pub paint: Paint,
+    pub border_width: f32,
+    pub border_color: glm::Vec4
+}
+
+#[allow(dead_code)]
+impl DrawRoundRect {
+    pub fn new(rect: RotateRect, paint: Paint) -> Self {
+        DrawRoundRect { rect, radii: Radii::all(0.), paint, border_width: 0., border_color: glm::vec4(0., 0., 0., 1.) }
+    }
+    pub fn radii(mut self, radii: Radii) -> Self {
+        self.radii = radii;
+        self
+    }
+    pub fn border(mut self, width: f32, color: glm::Vec4) -> Self {
+        self.border_width = width;
+        self.border_color = color;
+        self
+    }
+    pub fn draw(&self, ctx: &DrawCtx) {
+        let mut uniforms = ShapeUniforms::new(0, &self.rect);
+        uniforms.radii = self.radii.to_vec4();
+        uniforms.border_width = self.border_width;
+        uniforms.border_color = self.border_color;
+        let color = uniforms.set_paint(&self.paint);
+        draw_sdf(&self.rect, &color, &uniforms, ctx);
+    }
+    pub fn draw_shadow(&self, shadow: &Shadow, ctx: &DrawCtx) {
+        let grow = Point::new(shadow.spread, shadow.spread);
+        let rect = RotateRect::new(self.rect.offset + shadow.offset - grow, self.rect.size + grow * 2., self.rect.rot);
+        let mut uniforms = ShapeUniforms::new(0, &rect);
+        let r = self.radii;
+        uniforms.radii = Radii::new(r.tl + shadow.spread, r.tr + shadow.spread, r.br + shadow.spread, r.bl + shadow.spread).to_vec4();
+        uniforms.blur = shadow.blur;
+        uniforms.fringe += shadow.blur;
+        draw_sdf(&rect, &shadow.color, &uniforms, ctx);
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub struct TransformCache<C: PartialEq + Clone, T: SendUniforms + PartialEq + Clone> {
     pub trans: RefCell<Option<(C, T)>>,
@@ -599,21 +824,6 @@ impl RectTransform {
     }
 }
 
-#[derive(SendUniforms)]
-struct LineTransform {
-    point1: glm::Vec2, point2: glm::Vec2, projection: glm::Mat4
-}
-
-impl LineTransform {
-    fn new(l: &DrawLine, vp: &Point) -> Self {
-        LineTransform {
-            point1: l.p1.to_vec2(),
-            point2: l.p2.to_vec2(),
-            projection: glm::ortho(0., vp.x, vp.y, 0., -1., 1.)
-        }
-    }
-}
-
 pub trait InBounds {
     fn in_bounds(&self, p: &Point, vp: &Point) -> bool;
 }
@@ -706,6 +916,11 @@ impl Rect {
         self.c1 = *off;
         self.c2 = *off + size;
     }
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let c1 = Point::new(self.c1.x.max(other.c1.x), self.c1.y.max(other.c1.y));
+        let c2 = Point::new(self.c2.x.min(other.c2.x), self.c2.y.min(other.c2.y));
+        Rect { c1, c2: Point::new(c2.x.max(c1.x), c2.y.max(c1.y)) }
+    }
 }
 
 impl InBounds for Rect {
@@ -847,18 +1062,132 @@ pub enum DrawMode {
     Graph
 }
 
+//the clip in effect after a push: the scissor box is the intersection of every
+//pushed rect, rotated rects are also written to the stencil buffer
+#[derive(Clone)]
+struct ClipState {
+    scissor: Rect,
+    stencil: Option<RotateRect>,
+    depth: i32
+}
+
 pub struct DrawCtx {
     pub prim_map: PrimMap,
     pub prog_map: ProgMap,
+    pub line_stream: VertexStream,
+    pub path_stream: VertexStream,
+    //logical size of the window, device pixels are viewport * scale
     pub viewport: Point,
+    pub scale: f32,
+    pub theme: Theme,
     pub render_text: RenderText,
     pub draw_mode: DrawMode,
+    clip_stack: RefCell<Vec<ClipState>>,
 }
 
 impl DrawCtx {
-    pub fn new(viewport: &Point) -> DrawCtx {
-        DrawCtx { prim_map: prim_map(), prog_map: prog_map(), viewport: *viewport, render_text: RenderText::new().unwrap(), 
-                draw_mode: DrawMode::Canvas }
+    pub fn new(drawable_size: &Point, scale: f32, theme: Theme) -> DrawCtx {
+        let mut ctx = DrawCtx { prim_map: prim_map(), prog_map: prog_map(), line_stream: VertexStream::new(&[3]), 
+                  path_stream: VertexStream::new(&[2]), viewport: *drawable_size / scale, scale,
+                  render_text: RenderText::new(theme.font_size, scale).unwrap(), theme,
+                draw_mode: DrawMode::Canvas, clip_stack: RefCell::new(Vec::new()) };
+        ctx.set_viewport(drawable_size);
+        ctx
+    }
+    pub fn push_clip(&self, rect: &Rect) {
+        let top = self.clip_stack.borrow().last().cloned();
+        let state = match top {
+            Some(top) => ClipState { scissor: top.scissor.intersect(rect), stencil: None, depth: top.depth },
+            None => ClipState { scissor: rect.clone(), stencil: None, depth: 0 }
+        };
+        self.clip_stack.borrow_mut().push(state);
+        self.apply_scissor();
+    }
+    pub fn push_clip_rotated(&self, rect: &RotateRect) {
+        if rect.rot.0 == 0. {
+            return self.push_clip(&Rect::new(rect.offset, rect.offset + rect.size));
+        }
+        let bbox = Rect::bounding_box(&rect.verts(&self.viewport));
+        let top = self.clip_stack.borrow().last().cloned();
+        let (scissor, depth) = match top {
+            Some(top) => (top.scissor.intersect(&bbox), top.depth),
+            None => (bbox, 0)
+        };
+        self.clip_stack.borrow_mut().push(ClipState { scissor, stencil: Some(rect.clone()), depth: depth + 1 });
+        self.apply_scissor();
+        self.write_stencil(rect, depth, gl::INCR);
+    }
+    pub fn pop_clip(&self) {
+        let top = self.clip_stack.borrow().last().cloned();
+        if let Some(ClipState { stencil: Some(rect), depth, .. }) = top {
+            self.write_stencil(&rect, depth, gl::DECR);
+            unsafe {
+                gl::StencilFunc(gl::EQUAL, depth - 1, 0xFF);
+                if depth == 1 {
+                    gl::Disable(gl::STENCIL_TEST);
+                }
+            }
+        }
+        self.clip_stack.borrow_mut().pop();
+        self.apply_scissor();
+    }
+    pub fn set_viewport(&mut self, drawable_size: &Point) {
+        self.viewport = *drawable_size / self.scale;
+        unsafe { gl::Viewport(0, 0, drawable_size.x as GLint, drawable_size.y as GLint); }
+    }
+    pub fn set_scale(&mut self, scale: f32) {
+        if scale == self.scale {
+            return;
+        }
+        let drawable_size = self.viewport * self.scale;
+        self.scale = scale;
+        self.render_text = RenderText::new(self.theme.font_size, scale).unwrap();
+        self.set_viewport(&drawable_size);
+    }
+    pub fn set_theme(&mut self, theme: Theme) {
+        if theme.font_size != self.theme.font_size {
+            self.render_text = RenderText::new(theme.font_size, self.scale).unwrap();
+        }
+        self.theme = theme;
+    }
+    pub fn clip_rect(&self) -> Option<Rect> {
+        self.clip_stack.borrow().last().map(|c| c.scissor.clone())
+    }
+    fn apply_scissor(&self) {
+        unsafe {
+            match self.clip_rect() {
+                Some(r) => {
+                    let s = self.scale;
+                    gl::Enable(gl::SCISSOR_TEST);
+                    gl::Scissor((r.c1.x * s).floor() as GLint, ((self.viewport.y - r.c2.y) * s).floor() as GLint,
+                                (r.width() * s).ceil() as GLint, (r.height() * s).ceil() as GLint);
+                }
+                None => gl::Disable(gl::SCISSOR_TEST)
+            }
+        }
+    }
+    //applies op to the stencil inside rect wherever the stencil equals depth,
+    //then only lets through fragments at the resulting depth
+    fn write_stencil(&self, rect: &RotateRect, depth: i32, op: GLenum) {
+        let v = rect.verts(&self.viewport);
+        let verts = [
+            v[0].x, v[0].y, v[1].x, v[1].y, v[2].x, v[2].y,
+            v[0].x, v[0].y, v[2].x, v[2].y, v[3].x, v[3].y
+        ];
+        unsafe {
+            gl::Enable(gl::STENCIL_TEST);
+            gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+            gl::StencilMask(0xFF);
+            gl::StencilFunc(gl::EQUAL, depth, 0xFF);
+            gl::StencilOp(gl::KEEP, gl::KEEP, op);
+        }
+        draw_triangles(&verts, &glm::vec4(0., 0., 0., 0.), self);
+        unsafe {
+            gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+            gl::StencilOp(gl::KEEP, gl::KEEP, gl::KEEP);
+            let new_depth = if op == gl::INCR { depth + 1 } else { depth };
+            gl::StencilFunc(gl::EQUAL, new_depth, 0xFF);
+        }
     }
     #[allow(dead_code)]
     pub fn draw_circle(&self, radius: f32, center: Point, color: glm::Vec4, fill: bool) {
@@ -872,6 +1201,27 @@ impl DrawCtx {
         DrawPolygon { prim: PrimType::Rect, fill, color, rect }.draw(self);
     }
     #[allow(dead_code)]
+    pub fn fill_path(&self, path: &Path, rule: FillRule, color: glm::Vec4) {
+        DrawPath { path, color }.fill(rule, self);
+    }
+    #[allow(dead_code)]
+    pub fn stroke_path(&self, path: &Path, color: glm::Vec4, style: &StrokeStyle) {
+        DrawPath { path, color }.stroke(style, self);
+    }
+    #[allow(dead_code)]
+    pub fn draw_round_rect(&self, rect: Rect, radii: Radii, paint: Paint, border: Option<&Border>) {
+        let mut r = DrawRoundRect::new(RotateRect::from_rect(rect, Radians(0.)), paint).radii(radii);
+        if let Some(border) = border {
+            r = r.border(border.width.x, border.color);
+        }
+        r.draw(self);
+    }
+    #[allow(dead_code)]
+    pub fn draw_shadow(&self, rect: Rect, radii: Radii, shadow: &Shadow) {
+        DrawRoundRect::new(RotateRect::from_rect(rect, Radians(0.)), Paint::Solid(shadow.color))
+            .radii(radii).draw_shadow(shadow, self);
+    }
+    #[allow(dead_code)]
     pub fn draw_square(&self, side: f32, offset: Point, color: glm::Vec4, fill: bool, rot: Radians) {
         let rect = RotateRect::new(offset, Point::new(side, side), rot);
         DrawPolygon { prim: PrimType::Rect, fill, color, rect }.draw(self);
@@ -882,7 +1232,17 @@ impl DrawCtx {
     }
     #[allow(dead_code)]
     pub fn draw_line(&self, p1: Point, p2: Point, color: glm::Vec4, line_width: f32) {
-        DrawLine { p1, p2, line_width, color }.draw(self);
+        DrawLine { p1, p2, line_width, color, cap: LineCap::Butt }.draw(self);
+    }
+    #[allow(dead_code)]
+    pub fn draw_polyline(&self, pts: &[Point], closed: bool, color: glm::Vec4, style: &StrokeStyle) {
+        DrawPolyline { pts: pts.to_vec(), closed, color, style: style.clone() }.draw(self);
+    }
+    //one line of text from the top left of r, cut off at its edges
+    pub fn draw_text(&self, text: &str, r: Rect, params: &TextParams) {
+        self.push_clip(&r);
+        self.render_text.draw(text, params, &RotateRect::from_rect(r, Radians(0.)), self);
+        self.pop_clip();
     }
 }
 
@@ -986,6 +1346,10 @@ impl LineBuilder {
         self.l.line_width = width;
         self
     }
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.l.cap = cap;
+        self
+    }
     pub fn get(self) -> Shape { Shape::Line(self.l) }
 }
 
@@ -1008,20 +1372,50 @@ impl Border {
 pub struct BorderRect {
     pub size: Point,
     pub border: Border,
-    fill_color: glm::Vec4,
+    pub radius: f32,
+    pub outline: f32,
+    pub shadow: Option<Shadow>,
+    fill: Paint,
 }
 
+#[allow(dead_code)]
 impl BorderRect { 
     pub fn new(size: Point, fill_color: glm::Vec4, border: Border) -> Self {
-        BorderRect { size, fill_color, border }
+        BorderRect { size, fill: Paint::Solid(fill_color), border, radius: 0., outline: OUTLINE_WIDTH, shadow: None }
+    }
+    pub fn outline(mut self, width: f32) -> Self {
+        self.outline = width;
+        self
+    }
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+    pub fn paint(mut self, paint: Paint) -> Self {
+        self.fill = paint;
+        self
+    }
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        self.shadow = Some(shadow);
+        self
     }
     pub fn draw(&self, offset: Point, draw_ctx: &DrawCtx) {
-        let border = Rect::new(offset, offset + self.size + self.border.width * Point::new(2., 2.));
-        draw_ctx.draw_rect(border.clone(), self.fill_color, true, Radians(0.)); 
-        draw_ctx.draw_rect(border, self.border.color, false, Radians(0.)); 
+        let rect = RotateRect::new(offset, self.size + self.border.width * Point::new(2., 2.), Radians(0.));
+        let r = DrawRoundRect::new(rect, self.fill.clone())
+            .radii(Radii::all(self.radius))
+            .border(self.outline, self.border.color);
+        if let Some(ref shadow) = self.shadow {
+            r.draw_shadow(shadow, draw_ctx);
+        }
+        r.draw(draw_ctx);
     }
 }
 
+pub fn lighten(color: &glm::Vec4, amount: f32) -> glm::Vec4 {
+    let white = glm::vec4(1., 1., 1., color[3]);
+    glm::mix(color, &white, amount)
+}
+
 /*fn draw_triangle(radius: f32, center: &Point, color: (u8, u8, u8), draw_ctx: &DrawCtx) {
     let rect = RotateRect::new(Point::new(center.x - radius, center.y - radius), Point::new(radius * 2., radius * 2.), Radians(0.));
     DrawPolygon { prim: PrimType::Circle, fill: true, color: rgb_to_f32(color.0, color.1, color.2), rect }.draw(draw_ctx);
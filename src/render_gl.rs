@@ -2,7 +2,7 @@ extern crate gl;
 extern crate nalgebra_glm;
 use nalgebra_glm as glm;
 
-use gl::types::{GLuint, GLint, GLenum, GLchar};
+use gl::types::{GLuint, GLint, GLenum, GLchar, GLvoid, GLsizeiptr};
 use std::ffi::{CString, CStr};
 
 pub struct Program {
@@ -110,6 +110,59 @@ fn create_whitespace_cstring_with_len(len: usize) -> CString {
     unsafe { CString::from_vec_unchecked(buf) }
 }
 
+pub struct VertexStream {
+    vao: GLuint,
+    vbo: GLuint,
+}
+
+impl VertexStream {
+    pub fn new(attribs: &[GLint]) -> Self {
+        let mut vao: GLuint = 0;
+        let mut vbo: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            let stride = attribs.iter().sum::<GLint>() * std::mem::size_of::<f32>() as GLint;
+            let mut offset = 0;
+            for (i, n) in attribs.iter().enumerate() {
+                gl::EnableVertexAttribArray(i as GLuint);
+                gl::VertexAttribPointer(i as GLuint, *n, gl::FLOAT, gl::FALSE, stride,
+                    (offset * std::mem::size_of::<f32>()) as *const GLvoid);
+                offset += *n as usize;
+            }
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+        VertexStream { vao, vbo }
+    }
+    pub fn draw(&self, verts: &[f32], n_verts: usize, mode: GLenum) {
+        if n_verts == 0 {
+            return;
+        }
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                (verts.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
+                verts.as_ptr() as *const GLvoid, gl::STREAM_DRAW);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::DrawArrays(mode, 0, n_verts as GLint);
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+impl Drop for VertexStream {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
 pub struct GChar {
     cstr: CString
 }
@@ -147,6 +200,18 @@ unsafe fn get_uniform_location(prog_id: GLuint, name: &str) -> Result<i32, Strin
     return Ok(loc)
 }
 
+impl SendUniform for f32 {
+    unsafe fn uniform(&self, loc: GLint) {
+        gl::Uniform1f(loc, *self); 
+    }
+}
+
+impl SendUniform for i32 {
+    unsafe fn uniform(&self, loc: GLint) {
+        gl::Uniform1i(loc, *self); 
+    }
+}
+
 impl SendUniform for glm::Vec2 {
     unsafe fn uniform(&self, loc: GLint) {
         gl::Uniform2fv(loc, 1, self.as_ptr()); 
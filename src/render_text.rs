@@ -12,6 +12,7 @@ use gl::types::*;
 
 use crate::render_gl::{Program, Shader, SendUniform, SendUniforms};
 use crate::primitives::{Point, rgb_to_f32, Radians, Rect, RotateRect, TransformCache, DrawCtx} ;
+use crate::theme::{ThemeColor};
 use md_ui_derive::SendUniforms;
 
 fn buffer_char_data() -> (GLuint, GLuint) {
@@ -77,7 +78,7 @@ impl TextUniforms {
     }
 }
 
-pub type TextUniformsCache = TransformCache<(Point, Radians), TextUniforms>;
+pub type TextUniformsCache = TransformCache<(Point, Point, Radians, Point, Point, glm::Vec4), TextUniforms>;
 
 pub enum TextAlign {
     Left,
@@ -86,7 +87,9 @@ pub enum TextAlign {
 }
 
 pub struct TextParams {
-    pub color: glm::Vec4,
+    //overrides the theme color picked by role
+    pub color: Option<glm::Vec4>,
+    pub role: ThemeColor,
     pub scale: f32,
     pub align: TextAlign,
     pub trans: TextUniformsCache 
@@ -96,14 +99,19 @@ pub struct TextParams {
 impl TextParams {
     pub fn new() -> Self {
         TextParams {
-            color: glm::vec4(0.,0.,0.,0.),
+            color: None,
+            role: ThemeColor::Text,
             scale: 1.0,
             align: TextAlign::Left,
             trans: TextUniformsCache::new()
         }
     }
     pub fn color(mut self, r: u8, g: u8, b: u8) -> Self {
-        self.color = rgb_to_f32(r, g, b);
+        self.color = Some(rgb_to_f32(r, g, b));
+        self
+    }
+    pub fn role(mut self, role: ThemeColor) -> Self {
+        self.role = role;
         self
     }
     pub fn scale(mut self, scale: f32) -> Self {
@@ -114,8 +122,10 @@ impl TextParams {
         self.align = align;
         self
     }
-    pub fn get_uniforms(&self, text: &str, r: &RotateRect, rt: &RenderText, vp: &Point) -> TextUniforms
+    pub fn get_uniforms(&self, text: &str, r: &RotateRect, rt: &RenderText, ctx: &DrawCtx) -> TextUniforms
     {
+        let vp = &ctx.viewport;
+        let color = self.color.unwrap_or(ctx.theme.color(self.role));
         let mut off = rt.char_offset(self.scale);
         match self.align { 
             TextAlign::Left => {},
@@ -129,8 +139,8 @@ impl TextParams {
             }
         };
         self.trans.transform(
-            (r.offset, r.rot),
-            Box::new(move || TextUniforms::new(&self.color, r, &off, vp))
+            (r.offset, r.size, r.rot, off, *vp, color),
+            Box::new(move || TextUniforms::new(&color, r, &off, vp))
         )
     }
 }
@@ -140,18 +150,22 @@ pub struct RenderText {
     vao: GLuint,
     vbo: GLuint,
     prog: Program,
+    pixel_ratio: f32,
 }
 
 impl RenderText {
-    pub fn new() -> Result<Self, Box<dyn Error>> {
+    //glyphs are rasterized at font_size * ui_scale device pixels,
+    //all metrics are reported back in logical pixels
+    pub fn new(font_size: f32, ui_scale: f32) -> Result<Self, Box<dyn Error>> {
         unsafe { gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1); }
         let lib = Library::init()?;
         let face = lib.new_face("fonts/arial.ttf", 0).map_err(|e| format!("Could not load font face: {:?}", e))?;
         let mut char_map = HashMap::new();
-        face.set_char_size(12 * 64, 0, 50, 0).unwrap();
+        let pixel_size = ((font_size * ui_scale).round() as u32).max(1);
+        let pixel_ratio = pixel_size as f32 / font_size.max(1.);
+        face.set_pixel_sizes(0, pixel_size)?;
         for c in 0..=127 {
             face.load_char(c as usize, freetype::face::LoadFlag::RENDER).map_err(|e| format!("Could not load char {:?} {:?}", c, e))?;
-            face.set_pixel_sizes(0, 24)?;
             let glyph = face.glyph();
             let bitmap = glyph.bitmap();
             let mut texture: GLuint = 0;
@@ -183,10 +197,18 @@ impl RenderText {
         unsafe { gl::BindTexture(gl::TEXTURE_2D, 0); }
         let (vao, vbo) = buffer_char_data();
         let prog = get_char_program()?;
-        Ok(RenderText { char_map, vao, vbo, prog })
+        Ok(RenderText { char_map, vao, vbo, prog, pixel_ratio })
+    }
+    pub fn pixel_ratio(&self) -> f32 {
+        self.pixel_ratio
+    }
+    //converts a text scale into a factor from device glyph pixels to logical pixels
+    fn px(&self, scale: f32) -> f32 {
+        scale / self.pixel_ratio
     }
     pub fn draw(&self, text: &str, params: &TextParams, rect: &RotateRect, ctx: &DrawCtx) {
-        let (scale, uniforms) = (params.scale, params.get_uniforms(text, &rect, self, &ctx.viewport));
+        let (scale, uniforms) = (params.scale, params.get_uniforms(text, &rect, self, ctx));
+        let px = self.px(scale);
         self.prog.set_used();
         uniforms.send_uniforms(self.prog.id()).unwrap();
         unsafe {
@@ -202,26 +224,25 @@ impl RenderText {
             }
             let ch = &self.char_map[&(c as GLchar)];
             let offset = Point::new(
-                ch.bearing.x as f32 * scale,
-                (ch.size.y - ch.bearing.y) as f32 * scale);
-            let size = Point::new(scale, scale) * ch.size.into();
+                ch.bearing.x as f32 * px,
+                (ch.size.y - ch.bearing.y) as f32 * px);
+            let size = Point::from(ch.size) * px;
             let orig = char_pt + offset;
             let verts = [
                 [orig.x, orig.y - size.y, 0.0, 0.0],
                 [orig.x, orig.y, 0.0, 1.0],
-                [orig.x + size.x, orig.y, 1.0, 1.0],
-                [orig.x + size.x, orig.y - size.y, 1.0, 0.0]
+                [orig.x + size.x, orig.y - size.y, 1.0, 0.0],
+                [orig.x + size.x, orig.y, 1.0, 1.0]
             ];
             unsafe { 
-                gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
                 gl::BindTexture(gl::TEXTURE_2D, ch.texture);
                 gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
                 gl::BufferSubData(gl::ARRAY_BUFFER, 0, 
                     (std::mem::size_of::<f32>() * 4 * 4) as GLsizeiptr, verts.as_ptr() as *const GLvoid);
                 gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-                gl::DrawArrays(gl::QUADS, 0, 4);
+                gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
             }
-            char_pt.x += (ch.advance >> 6) as f32 * scale;
+            char_pt.x += (ch.advance >> 6) as f32 * px;
         }
         unsafe {
             gl::BindVertexArray(0);
@@ -232,19 +253,26 @@ impl RenderText {
         self.char_map.contains_key(&(ch as GLchar))
     }
     fn char_offset(&self, scale: f32) -> Point {
+        let scale = self.px(scale);
         let ch = &self.char_map[&('g' as i8)];
         Point::new(
             ch.bearing.x as f32 * scale,
             ch.size.y as f32 * scale)
     }
+    //distance from the top of a line to its baseline
+    pub fn baseline(&self, scale: f32) -> f32 {
+        self.char_offset(scale).y
+    }
     pub fn line_height(&self, scale: f32) -> f32 {
-        self.char_map[&('g' as i8)].size.y as f32 * 1.25 * scale
+        self.char_map[&('g' as i8)].size.y as f32 * 1.25 * self.px(scale)
     }
     pub fn char_size(&self, ch: char, scale: f32) -> Point {
+        let scale = self.px(scale);
         self.char_map.get(&(ch as GLchar)).map(|ch| Point::new(scale * ch.size.x as f32, scale * ch.size.y as f32))
             .unwrap_or(Point::origin())
     }
     pub fn char_size_w_advance(&self, ch: char, scale: f32) -> Point {
+        let scale = self.px(scale);
         self.char_map.get(&(ch as GLchar)).map(|ch| Point::new(scale * (ch.advance >> 6) as f32, scale * ch.size.y as f32))
             .unwrap_or(Point::origin())
     }
@@ -253,6 +281,7 @@ impl RenderText {
             return Point::origin();
         } 
         let lh = self.line_height(scale);
+        let scale = self.px(scale);
         text.bytes().fold(Point::new(0., lh), |size, c| {
             let ch = &self.char_map[&(c as GLchar)];
             size + match c as char {
@@ -263,3 +292,16 @@ impl RenderText {
     } 
 }
 
+//the DrawCtx builds a new RenderText whenever the scale or theme changes
+impl Drop for RenderText {
+    fn drop(&mut self) {
+        unsafe {
+            for ch in self.char_map.values() {
+                gl::DeleteTextures(1, &ch.texture);
+            }
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
@@ -0,0 +1,246 @@
+extern crate sdl2;
+
+use sdl2::keyboard::Keycode;
+use crate::primitives::{DrawCtx, Point, Rect, InBounds, Radii, Paint};
+use crate::interface::{EventCtx};
+use crate::widgets::{Widget, WidgetResponse, WidgetStatus, just_status, MDDoc};
+use crate::events::{MouseEvent};
+
+//logical pixels scrolled per wheel notch and per arrow key
+const SCROLL_STEP: f32 = 40.;
+//scrollbar thumbs never get shorter than this
+const MIN_THUMB: f32 = 20.;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Axis {
+    Horizontal,
+    Vertical
+}
+
+impl Axis {
+    fn get(&self, p: &Point) -> f32 {
+        match self { Axis::Horizontal => p.x, Axis::Vertical => p.y }
+    }
+    fn set(&self, p: &mut Point, v: f32) {
+        match self { Axis::Horizontal => p.x = v, Axis::Vertical => p.y = v }
+    }
+}
+
+struct ScrollState {
+    scroll: Point,
+    size: Point,
+    content: Point,
+    bar_width: f32,
+    //axis being dragged and the scroll offset when the drag started
+    drag: Option<(Axis, Point)>,
+}
+
+impl ScrollState {
+    fn max_scroll(&self) -> Point {
+        Point::new((self.content.x - self.size.x).max(0.), (self.content.y - self.size.y).max(0.))
+    }
+    fn scroll_to(&mut self, scroll: Point) -> bool {
+        let max = self.max_scroll();
+        let scroll = Point::new(scroll.x.max(0.).min(max.x), scroll.y.max(0.).min(max.y));
+        let changed = scroll != self.scroll;
+        self.scroll = scroll;
+        changed
+    }
+    fn has_bar(&self, axis: Axis) -> bool {
+        axis.get(&self.content) > axis.get(&self.size)
+    }
+    //the whole strip along the edge of the view the scrollbar slides in
+    fn track(&self, axis: Axis) -> Rect {
+        let w = self.bar_width;
+        match axis {
+            Axis::Vertical => Rect::new(Point::new(self.size.x - w, 0.), Point::new(self.size.x, self.size.y)),
+            Axis::Horizontal => Rect::new(Point::new(0., self.size.y - w), Point::new(self.size.x, self.size.y))
+        }
+    }
+    fn track_len(&self, axis: Axis) -> f32 {
+        axis.get(&self.size) - if self.has_bar(Axis::Horizontal) && self.has_bar(Axis::Vertical) { self.bar_width } else { 0. }
+    }
+    fn thumb_len(&self, axis: Axis) -> f32 {
+        let len = self.track_len(axis);
+        (len * axis.get(&self.size) / axis.get(&self.content)).max(MIN_THUMB).min(len)
+    }
+    fn thumb(&self, axis: Axis) -> Rect {
+        let track = self.track(axis);
+        let (len, thumb_len) = (self.track_len(axis), self.thumb_len(axis));
+        let max = axis.get(&self.max_scroll());
+        let start = if max > 0. { axis.get(&self.scroll) / max * (len - thumb_len) } else { 0. };
+        let (mut c1, mut c2) = (track.c1, track.c2);
+        axis.set(&mut c1, start);
+        axis.set(&mut c2, start + thumb_len);
+        Rect::new(c1, c2)
+    }
+    fn bar_at(&self, pt: &Point) -> Option<Axis> {
+        [Axis::Vertical, Axis::Horizontal].iter()
+            .find(|a| self.has_bar(**a) && self.track(**a).in_bounds(pt, &Point::origin()))
+            .copied()
+    }
+    //how far the content moves for every pixel the thumb moves
+    fn drag_ratio(&self, axis: Axis) -> f32 {
+        let free = self.track_len(axis) - self.thumb_len(axis);
+        if free > 0. { axis.get(&self.max_scroll()) / free } else { 0. }
+    }
+}
+
+//shows a fixed size window onto a child that may be larger, scrolled with the
+//mouse wheel, by dragging the scrollbars or with the page/arrow keys
+pub struct ScrollView {
+    child: Box<dyn Widget>,
+    state: ScrollState,
+}
+
+#[allow(dead_code)]
+impl ScrollView {
+    pub fn new(child: Box<dyn Widget>, size: Point, ctx: &DrawCtx) -> Self {
+        let content = child.measure(ctx);
+        let state = ScrollState { scroll: Point::origin(), size, content, bar_width: ctx.theme.scrollbar_width, drag: None };
+        ScrollView { child, state }
+    }
+    pub fn content(&self) -> &Box<dyn Widget> {
+        &self.child
+    }
+    pub fn content_mut(&mut self) -> &mut Box<dyn Widget> {
+        &mut self.child
+    }
+    pub fn scroll_offset(&self) -> Point {
+        self.state.scroll
+    }
+    pub fn set_size(&mut self, size: Point) {
+        self.state.size = size;
+        let scroll = self.state.scroll;
+        self.state.scroll_to(scroll);
+    }
+    pub fn scroll_by(&mut self, delta: &Point) -> bool {
+        let scroll = self.state.scroll + *delta;
+        self.state.scroll_to(scroll)
+    }
+    fn draw_bars(&self, off: &Point, ctx: &DrawCtx) {
+        let state = &self.state;
+        let theme = &ctx.theme;
+        let radii = Radii::all(state.bar_width / 2.);
+        for axis in [Axis::Vertical, Axis::Horizontal].iter() {
+            if !state.has_bar(*axis) {
+                continue;
+            }
+            let dragging = state.drag.map(|d| d.0 == *axis).unwrap_or(false);
+            let color = if dragging { theme.palette.focus } else { theme.palette.border };
+            let thumb = state.thumb(*axis);
+            ctx.draw_round_rect(Rect::new(*off + thumb.c1, *off + thumb.c2), radii, Paint::Solid(color), None);
+        }
+    }
+}
+
+impl Widget for ScrollView {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        self.state.size
+    }
+    fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
+        let natural = self.child.remeasure(ctx);
+        //stretch the child to fill the view, it only scrolls when it can't shrink to fit
+        let size = self.state.size;
+        self.child.arrange(&Point::new(natural.x.max(size.x), natural.y.max(size.y)), ctx);
+        let content = self.child.measure(ctx);
+        let state = &mut self.state;
+        state.content = content;
+        state.bar_width = ctx.theme.scrollbar_width;
+        let scroll = state.scroll;
+        state.scroll_to(scroll);
+        state.size
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        let (scroll, size) = (self.state.scroll, self.state.size);
+        ctx.push_clip(&Rect::new(*off, *off + size));
+        self.child.draw(&(*off - scroll), ctx);
+        self.draw_bars(off, ctx);
+        ctx.pop_clip();
+    }
+    //pressing a scrollbar captures the mouse until release, pressing the track
+    //outside the thumb first jumps a page towards the press
+    fn mouse_event(&mut self, ev: &MouseEvent, pt: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+        let state = &mut self.state;
+        match *ev {
+            MouseEvent::Press { .. } => {
+                let axis = state.bar_at(pt)?;
+                let thumb = state.thumb(axis);
+                if !thumb.in_bounds(pt, &Point::origin()) {
+                    let page = axis.get(&state.size);
+                    let dir = if axis.get(pt) < axis.get(&thumb.c1) { -1. } else { 1. };
+                    let mut scroll = state.scroll;
+                    axis.set(&mut scroll, axis.get(&state.scroll) + dir * page);
+                    state.scroll_to(scroll);
+                }
+                state.drag = Some((axis, state.scroll));
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            MouseEvent::Drag { ref delta, .. } => {
+                let (axis, start_scroll) = state.drag?;
+                let mut scroll = start_scroll;
+                axis.set(&mut scroll, axis.get(&start_scroll) + axis.get(delta) * state.drag_ratio(axis));
+                state.scroll_to(scroll);
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            MouseEvent::Release => {
+                state.drag.take()?;
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            _ => None
+        }
+    }
+    fn child_at(&self, pt: &Point, _: &DrawCtx) -> Option<usize> {
+        if self.state.bar_at(pt).is_some() { None } else { Some(0) }
+    }
+    fn child_offset(&self, _: usize) -> Point {
+        -self.state.scroll
+    }
+    fn child(&self, idx: usize) -> Option<&dyn Widget> {
+        if idx == 0 { Some(self.child.as_ref()) } else { None }
+    }
+    fn child_mut(&mut self, idx: usize) -> Option<&mut dyn Widget> {
+        if idx == 0 { Some(self.child.as_mut()) } else { None }
+    }
+    fn scroll(&mut self, pt: &Point, delta: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        let scroll = self.state.scroll;
+        if let Some(resp) = self.child.scroll(&(*pt + scroll), delta, ctx) {
+            return Some(resp)
+        }
+        if self.scroll_by(&(*delta * SCROLL_STEP)) {
+            Some(just_status(WidgetStatus::REDRAW))
+        }
+        else { None }
+    }
+    fn key_down(&mut self, kc: &Keycode, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        if let Some(resp) = self.child.key_down(kc, ctx) {
+            return Some(resp)
+        }
+        let (page, content) = (self.state.size.y, self.state.content);
+        let scroll = self.scroll_offset();
+        let target = match *kc {
+            Keycode::PageUp => Point::new(scroll.x, scroll.y - page),
+            Keycode::PageDown => Point::new(scroll.x, scroll.y + page),
+            Keycode::Home => Point::origin(),
+            Keycode::End => Point::new(scroll.x, content.y),
+            Keycode::Up => Point::new(scroll.x, scroll.y - SCROLL_STEP),
+            Keycode::Down => Point::new(scroll.x, scroll.y + SCROLL_STEP),
+            Keycode::Left => Point::new(scroll.x - SCROLL_STEP, scroll.y),
+            Keycode::Right => Point::new(scroll.x + SCROLL_STEP, scroll.y),
+            _ => return None
+        };
+        if self.state.scroll_to(target) {
+            Some(just_status(WidgetStatus::REDRAW))
+        }
+        else { None }
+    }
+    fn deselect(&mut self) -> Option<WidgetResponse> {
+        self.child.deselect()
+    }
+    fn do_serialize(&self) -> bool {
+        self.child.do_serialize()
+    }
+    fn serialize(&self, buf: &mut MDDoc) {
+        self.child.serialize(buf)
+    }
+}
@@ -0,0 +1,14 @@
+#version 330 core
+layout(location = 0) out vec4 shapeColor;
+
+in float edge;
+
+uniform vec4 color;
+uniform float half_width;
+
+void main()
+{
+    float aa = max(fwidth(edge), 1e-4);
+    float alpha = clamp((half_width - abs(edge)) / aa + 0.5, 0.0, 1.0);
+    shapeColor = vec4(color.rgb, color.a * alpha);
+}
\ No newline at end of file
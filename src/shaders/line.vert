@@ -1,7 +1,13 @@
 #version 330 core
 
-layout (location = 0) in vec4 Position;
+layout (location = 0) in vec3 vertex;
 
-void main() {
-    gl_Position = Position; 
+uniform mat4 projection;
+
+out float edge;
+
+void main()
+{
+    gl_Position = projection * vec4(vertex.xy, 0.0, 1.0);
+    edge = vertex.z;
 }
\ No newline at end of file
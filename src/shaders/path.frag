@@ -0,0 +1,9 @@
+#version 330 core
+layout(location = 0) out vec4 shapeColor;
+
+uniform vec4 color;
+
+void main()
+{
+    shapeColor = color;
+}
\ No newline at end of file
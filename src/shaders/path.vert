@@ -0,0 +1,9 @@
+#version 330 core
+
+layout (location = 0) in vec2 vertex;
+uniform mat4 projection;
+
+void main()
+{
+    gl_Position = projection * vec4(vertex, 0.0, 1.0);
+}
\ No newline at end of file
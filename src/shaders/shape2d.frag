@@ -1,9 +1,93 @@
 #version 330 core
 layout(location = 0) out vec4 shapeColor;
 
+in vec2 local;
+
 uniform vec4 color;
+uniform vec2 size;
+uniform int shape;
+uniform float stroke;
+uniform vec4 radii;
+uniform float blur;
+uniform float border_width;
+uniform vec4 border_color;
+uniform int paint;
+uniform vec4 color2;
+uniform vec2 grad_start;
+uniform vec2 grad_end;
+
+// radii are (top left, top right, bottom right, bottom left)
+float sd_round_box(vec2 p, vec2 b, vec4 r)
+{
+    float rad = p.x < 0.0 ? (p.y < 0.0 ? r.x : r.w) : (p.y < 0.0 ? r.y : r.z);
+    rad = min(rad, min(b.x, b.y));
+    vec2 q = abs(p) - b + rad;
+    return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - rad;
+}
+
+float sd_ellipse(vec2 p, vec2 r)
+{
+    float k0 = length(p / r);
+    float k1 = length(p / (r * r));
+    return k0 * (k0 - 1.0) / max(k1, 1e-6);
+}
+
+float sd_triangle(vec2 p, vec2 p0, vec2 p1, vec2 p2)
+{
+    vec2 e0 = p1 - p0, e1 = p2 - p1, e2 = p0 - p2;
+    vec2 v0 = p - p0, v1 = p - p1, v2 = p - p2;
+    vec2 pq0 = v0 - e0 * clamp(dot(v0, e0) / dot(e0, e0), 0.0, 1.0);
+    vec2 pq1 = v1 - e1 * clamp(dot(v1, e1) / dot(e1, e1), 0.0, 1.0);
+    vec2 pq2 = v2 - e2 * clamp(dot(v2, e2) / dot(e2, e2), 0.0, 1.0);
+    float s = sign(e0.x * e2.y - e0.y * e2.x);
+    vec2 d = min(min(vec2(dot(pq0, pq0), s * (v0.x * e0.y - v0.y * e0.x)),
+                     vec2(dot(pq1, pq1), s * (v1.x * e1.y - v1.y * e1.x))),
+                     vec2(dot(pq2, pq2), s * (v2.x * e2.y - v2.y * e2.x)));
+    return -sqrt(d.x) * sign(d.y);
+}
+
+float coverage(float d)
+{
+    if (blur > 0.0) {
+        return 1.0 - smoothstep(-blur, blur, d);
+    }
+    float aa = max(fwidth(d), 1e-4);
+    return clamp(0.5 - d / aa, 0.0, 1.0);
+}
+
+vec4 paint_color()
+{
+    if (paint == 1) {
+        vec2 g = grad_end - grad_start;
+        float t = clamp(dot(local - grad_start, g) / max(dot(g, g), 1e-6), 0.0, 1.0);
+        return mix(color, color2, t);
+    }
+    if (paint == 2) {
+        float t = clamp(length(local - grad_start) / max(length(grad_end - grad_start), 1e-6), 0.0, 1.0);
+        return mix(color, color2, t);
+    }
+    return color;
+}
 
 void main()
 {
-    shapeColor = color;
+    vec2 b = size * 0.5;
+    float d;
+    if (shape == 1) {
+        d = sd_ellipse(local, b);
+    }
+    else if (shape == 2) {
+        d = sd_triangle(local, vec2(-b.x, b.y), vec2(b.x, b.y), vec2(0.0, -b.y));
+    }
+    else {
+        d = sd_round_box(local, b, radii);
+    }
+    if (stroke > 0.0) {
+        d = abs(d + stroke * 0.5) - stroke * 0.5;
+    }
+    vec4 fill = paint_color();
+    if (border_width > 0.0) {
+        fill = mix(border_color, fill, coverage(d + border_width));
+    }
+    shapeColor = vec4(fill.rgb, fill.a * coverage(d));
 }
\ No newline at end of file
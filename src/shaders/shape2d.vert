@@ -3,8 +3,15 @@
 layout (location = 0) in vec2 vertex;
 uniform mat4 model;
 uniform mat4 projection;
+uniform vec2 size;
+uniform float fringe;
+
+out vec2 local;
 
 void main()
 {
-    gl_Position = vec4(projection * model * vec4(vertex, 0.0, 1.0));
+    vec2 grow = vec2(fringe) / max(size, vec2(1e-4));
+    vec2 v = vertex * (1.0 + 2.0 * grow) - grow;
+    local = (v - 0.5) * size;
+    gl_Position = vec4(projection * model * vec4(v, 0.0, 1.0));
 }
\ No newline at end of file
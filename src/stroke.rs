@@ -0,0 +1,390 @@
+extern crate gl;
+extern crate nalgebra_glm;
+
+use gl::types::GLuint;
+use nalgebra_glm as glm;
+use std::f32::consts::PI;
+use crate::primitives::{Point, PrimType, DrawCtx};
+use crate::render_gl::{SendUniform, SendUniforms};
+use md_ui_derive::SendUniforms;
+
+//extra width on each side of a stroke that the line shader fades out for anti-aliasing
+const AA_FRINGE: f32 = 1.0;
+//max distance in pixels between a flattened arc and the true arc
+const ARC_TOLERANCE: f32 = 0.25;
+const MAX_ARC_SEGMENTS: usize = 100;
+//shortest dash or gap, shorter ones would take a step per pattern unit along the line
+const MIN_DASH: f32 = 1.;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct StrokeStyle {
+    pub width: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub miter_limit: f32,
+    pub dash: Vec<f32>,
+    pub dash_offset: f32
+}
+
+#[allow(dead_code)]
+impl StrokeStyle {
+    pub fn new(width: f32) -> Self {
+        StrokeStyle {
+            width,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.,
+            dash: Vec::new(),
+            dash_offset: 0.
+        }
+    }
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+    pub fn miter_limit(mut self, limit: f32) -> Self {
+        self.miter_limit = limit;
+        self
+    }
+    //negative and non-finite entries count as 0, see dash_len
+    pub fn dash(mut self, pattern: &[f32], offset: f32) -> Self {
+        self.dash = pattern.iter().map(|d| dash_len(*d)).collect();
+        self.dash_offset = if offset.is_finite() { offset } else { 0. };
+        self
+    }
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        StrokeStyle::new(3.)
+    }
+}
+
+//0 stays 0 so dots can be drawn with round caps, other lengths are at least MIN_DASH
+fn dash_len(d: f32) -> f32 {
+    if !d.is_finite() || d <= 0. { 0. } else { d.max(MIN_DASH) }
+}
+
+pub fn arc_segments(radius: f32, angle: f32) -> usize {
+    if !radius.is_finite() || !angle.is_finite() {
+        return 1
+    }
+    let step = 2. * f32::acos(1. - ARC_TOLERANCE / radius.max(ARC_TOLERANCE));
+    ((angle.abs() / step).ceil() as usize).max(1).min(MAX_ARC_SEGMENTS)
+}
+
+struct StrokeMesh<'a> {
+    verts: Vec<f32>,
+    style: &'a StrokeStyle,
+    //half width of the emitted geometry, including the AA fringe
+    hw: f32
+}
+
+impl<'a> StrokeMesh<'a> {
+    fn vert(&mut self, p: Point, edge: f32) {
+        self.verts.extend_from_slice(&[p.x, p.y, edge]);
+    }
+    fn tri(&mut self, a: (Point, f32), b: (Point, f32), c: (Point, f32)) {
+        self.vert(a.0, a.1);
+        self.vert(b.0, b.1);
+        self.vert(c.0, c.1);
+    }
+    fn fan(&mut self, center: Point, start: f32, sweep: f32) {
+        let hw = self.hw;
+        let n = arc_segments(hw, sweep);
+        let rim = |i: usize| {
+            let a = start + sweep * i as f32 / n as f32;
+            center + Point::new(a.cos(), a.sin()) * hw
+        };
+        for i in 0..n {
+            self.tri((center, 0.), (rim(i), hw), (rim(i + 1), hw));
+        }
+    }
+    fn segment(&mut self, a: Point, b: Point) {
+        let n = (b - a).normalize().perp() * self.hw;
+        let hw = self.hw;
+        self.tri((a + n, hw), (a - n, -hw), (b + n, hw));
+        self.tri((a - n, -hw), (b - n, -hw), (b + n, hw));
+    }
+    fn join(&mut self, prev: Point, p: Point, next: Point) {
+        let d0 = (p - prev).normalize();
+        let d1 = (next - p).normalize();
+        let cross = d0.cross(&d1);
+        if cross.abs() < 1e-6 && d0.dot(&d1) > 0. {
+            return;
+        }
+        let side = if cross > 0. { -1. } else { 1. };
+        let n0 = d0.perp() * side;
+        let n1 = d1.perp() * side;
+        let hw = self.hw;
+        let (p0, p1) = (p + n0 * hw, p + n1 * hw);
+        match self.style.join {
+            LineJoin::Round => {
+                let start = n0.y.atan2(n0.x);
+                let mut sweep = n1.y.atan2(n1.x) - start;
+                if sweep > PI { sweep -= 2. * PI; }
+                if sweep < -PI { sweep += 2. * PI; }
+                self.fan(p, start, sweep);
+            }
+            LineJoin::Miter => {
+                let m = (n0 + n1).normalize();
+                let cos_half = m.dot(&n0);
+                if cos_half < 1e-4 || 1. / cos_half > self.style.miter_limit {
+                    self.tri((p, 0.), (p0, hw), (p1, hw));
+                }
+                else {
+                    let tip = p + m * (hw / cos_half);
+                    self.tri((p, 0.), (p0, hw), (tip, hw));
+                    self.tri((p, 0.), (tip, hw), (p1, hw));
+                }
+            }
+            LineJoin::Bevel => {
+                self.tri((p, 0.), (p0, hw), (p1, hw));
+            }
+        }
+    }
+    fn dot(&mut self, p: Point) {
+        let half = self.style.width / 2.;
+        match self.style.cap {
+            LineCap::Round => self.fan(p, 0., 2. * PI),
+            LineCap::Square => {
+                self.segment(p - Point::new(half, 0.), p + Point::new(half, 0.));
+            }
+            LineCap::Butt => {}
+        }
+    }
+    fn polyline(&mut self, pts: &[Point], mut closed: bool) {
+        let n = pts.len();
+        if n == 0 {
+            return;
+        }
+        if n == 1 {
+            self.dot(pts[0]);
+            return;
+        }
+        if n == 2 {
+            closed = false;
+        }
+        let half = self.style.width / 2.;
+        let n_seg = if closed { n } else { n - 1 };
+        for i in 0..n_seg {
+            let (mut a, mut b) = (pts[i], pts[(i + 1) % n]);
+            if !closed && self.style.cap == LineCap::Square {
+                let dir = (b - a).normalize();
+                if i == 0 { a -= dir * half; }
+                if i == n_seg - 1 { b += dir * half; }
+            }
+            self.segment(a, b);
+        }
+        let joints = if closed { 0..n } else { 1..n-1 };
+        for i in joints {
+            self.join(pts[(i + n - 1) % n], pts[i], pts[(i + 1) % n]);
+        }
+        if !closed && self.style.cap == LineCap::Round {
+            let d0 = (pts[1] - pts[0]).normalize().perp();
+            self.fan(pts[0], d0.y.atan2(d0.x), PI);
+            let d1 = -(pts[n - 1] - pts[n - 2]).normalize().perp();
+            self.fan(pts[n - 1], d1.y.atan2(d1.x), PI);
+        }
+    }
+}
+
+fn dedup_points(pts: &[Point], closed: bool) -> Vec<Point> {
+    let mut out: Vec<Point> = Vec::with_capacity(pts.len());
+    for p in pts {
+        if out.last().map(|l| l.dist(p) > 1e-4).unwrap_or(true) {
+            out.push(*p);
+        }
+    }
+    if closed && out.len() > 1 && out[0].dist(&out[out.len() - 1]) <= 1e-4 {
+        out.pop();
+    }
+    out
+}
+
+fn dash_polyline(pts: &[Point], closed: bool, pattern: &[f32], offset: f32) -> Vec<Vec<Point>> {
+    //the style's fields are public so the pattern is checked again here
+    let pattern: Vec<f32> = pattern.iter().map(|d| dash_len(*d)).collect();
+    let pattern = if pattern.len() % 2 == 1 { pattern.repeat(2) } else { pattern };
+    let total: f32 = pattern.iter().sum();
+    let offset = if offset.is_finite() { offset } else { 0. };
+    if pts.is_empty() || total <= 0. {
+        return vec![pts.to_vec()];
+    }
+    let mut pts = pts.to_vec();
+    if closed {
+        pts.push(pts[0]);
+    }
+    let mut idx = 0;
+    let mut remaining = pattern[0];
+    let mut phase = offset.rem_euclid(total);
+    while phase > 0. {
+        if phase >= remaining {
+            phase -= remaining;
+            idx = (idx + 1) % pattern.len();
+            remaining = pattern[idx];
+        }
+        else {
+            remaining -= phase;
+            phase = 0.;
+        }
+    }
+    let mut dashes = Vec::new();
+    let mut current = if idx % 2 == 0 { vec![pts[0]] } else { Vec::new() };
+    for w in pts.windows(2) {
+        let (mut a, b) = (w[0], w[1]);
+        let mut seg_len = a.dist(&b);
+        while seg_len > 0. {
+            let step = remaining.min(seg_len);
+            a = a + (b - a) * (step / seg_len);
+            seg_len -= step;
+            remaining -= step;
+            if idx % 2 == 0 {
+                current.push(a);
+            }
+            if remaining <= 1e-6 {
+                if idx % 2 == 0 && !current.is_empty() {
+                    dashes.push(std::mem::replace(&mut current, Vec::new()));
+                }
+                idx = (idx + 1) % pattern.len();
+                remaining = pattern[idx];
+                if idx % 2 == 0 {
+                    current = vec![a];
+                }
+            }
+        }
+    }
+    if current.len() > 1 {
+        dashes.push(current);
+    }
+    dashes
+}
+
+pub fn stroke_verts(pts: &[Point], closed: bool, style: &StrokeStyle) -> Vec<f32> {
+    let mut mesh = StrokeMesh { verts: Vec::new(), style, hw: style.width / 2. + AA_FRINGE };
+    let pts = dedup_points(pts, closed);
+    if style.dash.iter().any(|d| dash_len(*d) > 0.) {
+        for dash in dash_polyline(&pts, closed, &style.dash, style.dash_offset) {
+            mesh.polyline(&dedup_points(&dash, false), false);
+        }
+    }
+    else {
+        mesh.polyline(&pts, closed);
+    }
+    mesh.verts
+}
+
+#[derive(SendUniforms)]
+struct StrokeUniforms {
+    projection: glm::Mat4,
+    color: glm::Vec4,
+    half_width: f32
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawPolyline {
+    pub pts: Vec<Point>,
+    pub closed: bool,
+    pub style: StrokeStyle,
+    pub color: glm::Vec4
+}
+
+impl DrawPolyline {
+    pub fn draw(&self, ctx: &DrawCtx) {
+        let verts = stroke_verts(&self.pts, self.closed, &self.style);
+        draw_stroke(&verts, &self.color, &self.style, ctx);
+    }
+}
+
+pub fn draw_stroke(verts: &[f32], color: &glm::Vec4, style: &StrokeStyle, ctx: &DrawCtx) {
+    let prog = &ctx.prog_map[&PrimType::Line];
+    prog.set_used();
+    let vp = ctx.viewport;
+    StrokeUniforms {
+        projection: glm::ortho(0., vp.x, vp.y, 0., -1., 1.),
+        color: *color,
+        half_width: style.width / 2.
+    }.send_uniforms(prog.id()).unwrap();
+    ctx.line_stream.draw(verts, verts.len() / 3, gl::TRIANGLES);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(len: f32) -> Vec<Point> {
+        vec![Point::new(0., 0.), Point::new(len, 0.)]
+    }
+
+    //start and end x of every dash along a horizontal line
+    fn spans(dashes: &[Vec<Point>]) -> Vec<(f32, f32)> {
+        dashes.iter().map(|d| (d[0].x, d[d.len() - 1].x)).collect()
+    }
+
+    #[test]
+    fn dashes_follow_the_pattern() {
+        let dashes = dash_polyline(&line(50.), false, &[10., 10.], 0.);
+        assert_eq!(spans(&dashes), vec![(0., 10.), (20., 30.), (40., 50.)]);
+    }
+
+    #[test]
+    fn offset_shifts_the_pattern() {
+        let dashes = dash_polyline(&line(30.), false, &[10., 10.], 5.);
+        assert_eq!(spans(&dashes), vec![(0., 5.), (15., 25.)]);
+        //an odd pattern is repeated, so dashes and gaps swap every other time
+        let dashes = dash_polyline(&line(30.), false, &[10.], 0.);
+        assert_eq!(spans(&dashes), vec![(0., 10.), (20., 30.)]);
+    }
+
+    #[test]
+    fn dash_continues_around_corners() {
+        let pts = vec![Point::new(0., 0.), Point::new(5., 0.), Point::new(5., 5.)];
+        let dashes = dash_polyline(&pts, false, &[8., 2.], 0.);
+        assert_eq!(dashes[0], vec![Point::new(0., 0.), Point::new(5., 0.), Point::new(5., 3.)]);
+    }
+
+    #[test]
+    fn bad_entries_count_as_zero() {
+        let style = StrokeStyle::new(2.).dash(&[-5., f32::NAN, f32::INFINITY, 3.], f32::NAN);
+        assert_eq!(style.dash, vec![0., 0., 0., 3.]);
+        assert_eq!(style.dash_offset, 0.);
+        //nothing left to dash, the line is drawn solid
+        assert_eq!(dash_polyline(&line(10.), false, &[-1., f32::NAN], 0.), vec![line(10.)]);
+        //negative dashes become dots
+        let dots = dash_polyline(&line(20.), false, &[-5., 10.], 0.);
+        assert!(dots.iter().all(|d| d[0] == d[d.len() - 1]));
+    }
+
+    #[test]
+    fn tiny_dashes_are_clamped() {
+        let dashes = dash_polyline(&line(1000.), false, &[1e-7, 1e-7], 0.);
+        assert_eq!(dashes.len(), 500);
+        assert!(!stroke_verts(&line(1000.), false, &StrokeStyle::new(1.).dash(&[1e-7, 1e-7], 0.)).is_empty());
+    }
+
+    #[test]
+    fn arc_segments_are_bounded() {
+        assert_eq!(arc_segments(f32::INFINITY, PI), 1);
+        assert_eq!(arc_segments(10., f32::NAN), 1);
+        assert_eq!(arc_segments(10., 1e9), MAX_ARC_SEGMENTS);
+        assert!(arc_segments(10., 2. * PI) > 1);
+    }
+}
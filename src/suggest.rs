@@ -0,0 +1,87 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use sdl2::mouse::SystemCursor;
+use crate::primitives::{DrawCtx, Point, Rect, Radians, Radii, Border, Paint};
+use crate::interface::{EventCtx};
+use crate::events::{MouseEvent};
+use crate::widgets::{Widget, WidgetResponse, WidgetStatus, just_status};
+
+//what a text field shares with its SuggestionList, one row per current match
+pub trait Suggest {
+    fn rows(&self) -> usize;
+    fn item_size(&self) -> Point;
+    fn highlight(&self) -> Option<usize>;
+    fn set_highlight(&mut self, row: Option<usize>);
+    fn draw_row(&self, row: usize, r: Rect, ctx: &DrawCtx);
+    //the row was clicked, the field takes its value
+    fn pick_row(&mut self, row: usize) -> WidgetResponse;
+}
+
+//the suggestions shown in the overlay below a text field while it has focus.
+//The list stays open after a pick, the field closes it when it loses focus
+pub struct SuggestionList<S: Suggest>(pub Rc<RefCell<S>>);
+
+impl<S: Suggest> SuggestionList<S> {
+    fn row_at(&self, pt: &Point) -> Option<usize> {
+        let state = self.0.borrow();
+        let item = state.item_size();
+        if pt.x < 0. || pt.x > item.x || pt.y < 0. {
+            return None
+        }
+        let row = (pt.y / item.y) as usize;
+        if row < state.rows() { Some(row) } else { None }
+    }
+}
+
+impl<S: Suggest> Widget for SuggestionList<S> {
+    //nothing to show takes no space so the overlay doesn't hit it
+    fn measure(&self, _: &DrawCtx) -> Point {
+        let state = self.0.borrow();
+        let item = state.item_size();
+        Point::new(item.x, item.y * state.rows() as f32)
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        let state = self.0.borrow();
+        if state.rows() == 0 {
+            return
+        }
+        let theme = &ctx.theme;
+        let radii = Radii::all(theme.radius);
+        let r = Rect::new(*off, *off + self.measure(ctx));
+        let border = Border::new(Point::new(theme.border_width, theme.border_width), theme.palette.focus);
+        ctx.draw_shadow(r.clone(), radii, &theme.shadow(3., 4.));
+        ctx.draw_round_rect(r, radii, Paint::Solid(theme.palette.widget_bg), Some(&border));
+        let item_size = state.item_size();
+        for row in 0..state.rows() {
+            let c1 = *off + Point::new(0., row as f32 * item_size.y);
+            let item = Rect::new(c1, c1 + item_size);
+            if state.highlight() == Some(row) {
+                ctx.draw_rect(item.clone(), theme.palette.hover, true, Radians(0.));
+            }
+            state.draw_row(row, item, ctx);
+        }
+    }
+    fn mouse_event(&mut self, ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        let row = self.row_at(pt);
+        let mut state = self.0.borrow_mut();
+        match (*ev, row) {
+            (MouseEvent::Move, _) => {
+                *ctx.cursor = SystemCursor::Hand;
+                if row.is_none() || row == state.highlight() {
+                    return Some(just_status(WidgetStatus::FINE))
+                }
+                state.set_highlight(row);
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            (MouseEvent::Press { .. }, Some(row)) => {
+                state.set_highlight(Some(row));
+                Some(state.pick_row(row))
+            }
+            _ => Some(just_status(WidgetStatus::FINE))
+        }
+    }
+    fn deselect(&mut self) -> Option<WidgetResponse> {
+        self.0.borrow_mut().set_highlight(None);
+        Some(just_status(WidgetStatus::REDRAW))
+    }
+}
@@ -0,0 +1,361 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::{SystemTime};
+use sdl2::mouse::SystemCursor;
+use sdl2::keyboard::Keycode;
+use crate::primitives::{DrawCtx, Point, Rect, RotateRect, Radians, Paint, DrawRoundRect};
+use crate::render_text::{TextParams};
+use crate::theme::{ThemeColor};
+use crate::textedit::{TextEdit, Blink};
+use crate::overlay::{Popup, PopupId, Anchor, new_anchor};
+use crate::suggest::{Suggest, SuggestionList};
+use crate::interface::{EventCtx};
+use crate::events::{MouseEvent};
+use crate::widgets::{Widget, WidgetResponse, WidgetStatus, Message, just_status, MDDoc};
+
+//suggestions shown below the field at once
+const MAX_SUGGESTIONS: usize = 8;
+//the suggestion list is never wider than this many chars
+const MAX_ROW_CHARS: usize = 40;
+
+pub struct SymbolInfo {
+    pub ticker: String,
+    pub name: String,
+    pub sector: String
+}
+
+//the symbols we trade, read from a csv of ticker, name, sector
+pub struct Watchlist {
+    pub symbols: Vec<SymbolInfo>
+}
+
+#[allow(dead_code)]
+impl Watchlist {
+    pub fn empty() -> Self {
+        Watchlist { symbols: Vec::new() }
+    }
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Could not read watchlist {}: {:?}", path, e))?;
+        Ok(Watchlist::parse(&text))
+    }
+    //the first line is skipped if it is a header
+    pub fn parse(text: &str) -> Self {
+        let symbols = text.lines()
+            .map(split_csv_line)
+            .filter(|f| !f.is_empty() && !f[0].is_empty() && !f[0].eq_ignore_ascii_case("ticker"))
+            .map(|f| {
+                let field = |i: usize| f.get(i).cloned().unwrap_or_default();
+                SymbolInfo { ticker: field(0).to_uppercase(), name: field(1), sector: field(2) }
+            })
+            .collect();
+        Watchlist { symbols }
+    }
+    pub fn get(&self, ticker: &str) -> Option<&SymbolInfo> {
+        self.symbols.iter().find(|s| s.ticker.eq_ignore_ascii_case(ticker))
+    }
+    //tickers starting with the text come first, then names containing it
+    pub fn search(&self, text: &str) -> Vec<usize> {
+        let text = text.trim().to_uppercase();
+        let by_ticker = (0..self.symbols.len()).filter(|i| self.symbols[*i].ticker.starts_with(&text));
+        let by_name = (0..self.symbols.len()).filter(|i| {
+            !self.symbols[*i].ticker.starts_with(&text) && self.symbols[*i].name.to_uppercase().contains(&text)
+        });
+        by_ticker.chain(by_name).take(MAX_SUGGESTIONS).collect()
+    }
+}
+
+//fields may be quoted so names like "Amazon.com, Inc." keep their comma
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::replace(&mut field, String::new()).trim().to_string()),
+            _ => field.push(c)
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+//shared by the field and its suggestion list in the overlay
+struct SymbolState {
+    watchlist: Rc<Watchlist>,
+    edit: TextEdit,
+    //indices into the watchlist, empty once a symbol was picked
+    matches: Vec<usize>,
+    highlight: Option<usize>,
+    item_size: Point,
+    ticker_width: f32,
+    popup: PopupId,
+}
+
+impl SymbolState {
+    fn refresh(&mut self) {
+        let text = self.edit.text();
+        self.matches = if text.trim().is_empty() { Vec::new() } else { self.watchlist.search(&text) };
+        self.highlight = if self.matches.is_empty() { None } else { Some(0) };
+    }
+    fn set_symbol(&mut self, ticker: &str) -> bool {
+        let ticker = ticker.trim().to_uppercase();
+        let changed = ticker != self.edit.text();
+        self.edit.set_text(&ticker);
+        self.matches.clear();
+        self.highlight = None;
+        changed
+    }
+    //takes the highlighted suggestion, or what was typed in uppercase
+    fn pick(&mut self) -> bool {
+        let ticker = match self.highlight.and_then(|h| self.matches.get(h)) {
+            Some(i) => self.watchlist.symbols[*i].ticker.clone(),
+            None => self.edit.text()
+        };
+        self.set_symbol(&ticker)
+    }
+    fn move_highlight(&mut self, delta: i32) {
+        if self.matches.is_empty() {
+            return
+        }
+        let last = self.matches.len() as i32 - 1;
+        self.highlight = Some(match self.highlight {
+            Some(h) => (h as i32 + delta).max(0).min(last) as usize,
+            None => 0
+        });
+    }
+}
+
+fn text_changed(changed: bool) -> WidgetResponse {
+    let resp = just_status(WidgetStatus::REDRAW);
+    if changed { resp.msg(Message::Changed) } else { resp }
+}
+
+//each suggestion shows the ticker and the name of the symbol
+impl Suggest for SymbolState {
+    fn rows(&self) -> usize {
+        self.matches.len()
+    }
+    fn item_size(&self) -> Point {
+        self.item_size
+    }
+    fn highlight(&self) -> Option<usize> {
+        self.highlight
+    }
+    fn set_highlight(&mut self, row: Option<usize>) {
+        self.highlight = row;
+    }
+    fn draw_row(&self, row: usize, r: Rect, ctx: &DrawCtx) {
+        let info = &self.watchlist.symbols[self.matches[row]];
+        let pad = ctx.theme.padding.x;
+        let name_x = r.c1.x + pad * 2. + self.ticker_width;
+        ctx.draw_text(&info.ticker, Rect::new(r.c1 + Point::new(pad, 0.), Point::new(name_x, r.c2.y)), &TextParams::new());
+        ctx.draw_text(&info.name, Rect::new(Point::new(name_x, r.c1.y), r.c2), &TextParams::new().role(ThemeColor::Border));
+    }
+    fn pick_row(&mut self, _: usize) -> WidgetResponse {
+        text_changed(self.pick())
+    }
+}
+
+//a ticker field that suggests symbols from the watchlist as you type and stores them in uppercase.
+//With describe set the name and sector of a watchlist symbol also go into the front matter
+pub struct SymbolInput {
+    state: Rc<RefCell<SymbolState>>,
+    list: Rc<RefCell<SuggestionList<SymbolState>>>,
+    anchor: Anchor,
+    select_time: Option<SystemTime>,
+    blink: Blink,
+    describe: bool,
+    size: Point,
+    natural_size: Point,
+}
+
+#[allow(dead_code)]
+impl SymbolInput {
+    pub fn new(watchlist: &Rc<Watchlist>, num_chars: usize, ctx: &DrawCtx) -> Self {
+        let size = ctx.render_text.measure(&"A".repeat(num_chars.max(1)), 1.);
+        let state = SymbolState { watchlist: watchlist.clone(), edit: TextEdit::new("", size), matches: Vec::new(),
+            highlight: None, item_size: Point::origin(), ticker_width: 0., popup: PopupId::new() };
+        let state = Rc::new(RefCell::new(state));
+        let list = Rc::new(RefCell::new(SuggestionList(state.clone())));
+        let mut w = SymbolInput { state, list, anchor: new_anchor(), select_time: None, blink: Blink::new(), describe: false, size, natural_size: size };
+        w.remeasure(ctx);
+        w
+    }
+    pub fn describe(mut self, describe: bool) -> Self {
+        self.describe = describe;
+        self
+    }
+    pub fn symbol(&self) -> String {
+        self.state.borrow().edit.text()
+    }
+    fn set_size(&mut self, size: Point) {
+        self.size = size;
+        self.state.borrow_mut().edit.set_size(size);
+    }
+    fn rect(&self, off: &Point) -> RotateRect {
+        RotateRect::from_rect(Rect::new(*off, *off + self.size), Radians(0.))
+    }
+    fn open(&self) -> Message {
+        let widget: Rc<RefCell<dyn Widget>> = self.list.clone();
+        let popup = self.state.borrow().popup;
+        Message::OpenPopup(Popup::new(popup, widget, Rc::clone(&self.anchor), Point::new(0., 2.)))
+    }
+}
+
+pub fn new_symbol_input(watchlist: &Rc<Watchlist>, num_chars: usize, ctx: &DrawCtx) -> Box<dyn Widget> {
+    Box::new(SymbolInput::new(watchlist, num_chars, ctx))
+}
+
+impl Widget for SymbolInput {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        self.size
+    }
+    //the suggestion rows are as wide as the longest ticker and name
+    fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
+        let size = self.natural_size;
+        self.set_size(size);
+        let mut state = self.state.borrow_mut();
+        let rt = &ctx.render_text;
+        let pad = ctx.theme.padding.x;
+        let watchlist = state.watchlist.clone();
+        state.ticker_width = watchlist.symbols.iter().fold(0f32, |w, s| w.max(rt.measure(&s.ticker, 1.).x));
+        let name_width = watchlist.symbols.iter().fold(0f32, |w, s| w.max(rt.measure(&s.name, 1.).x));
+        let max_width = rt.measure(&"A".repeat(MAX_ROW_CHARS), 1.).x;
+        let width = (state.ticker_width + name_width + pad * 3.).min(max_width).max(size.x);
+        state.item_size = Point::new(width, rt.line_height(1.));
+        size
+    }
+    fn arrange(&mut self, size: &Point, _: &DrawCtx) {
+        let size = Point::new(size.x.max(self.natural_size.x), self.natural_size.y);
+        self.set_size(size);
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        Some(ctx.render_text.baseline(1.))
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        self.anchor.set(*off + Point::new(0., self.size.y));
+        let theme = &ctx.theme;
+        let (border_width, border_color) = match self.select_time {
+            Some(_) => (theme.focus_width, theme.palette.focus),
+            None => (theme.border_width, theme.palette.border)
+        };
+        let rect = self.rect(off);
+        DrawRoundRect::new(rect.clone(), Paint::Solid(theme.palette.widget_bg))
+            .border(border_width, border_color)
+            .draw(ctx);
+        ctx.push_clip_rotated(&rect);
+        self.state.borrow().edit.draw(&rect, self.select_time.is_some() && self.blink.visible(), ctx);
+        ctx.pop_clip();
+    }
+    fn mouse_event(&mut self, ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        match *ev {
+            MouseEvent::Move => {
+                *ctx.cursor = SystemCursor::IBeam;
+                return Some(just_status(WidgetStatus::FINE))
+            }
+            MouseEvent::Press { .. } => (),
+            _ => return None
+        }
+        let focus = self.select_time.is_none();
+        self.select_time = Some(SystemTime::now());
+        {
+            let mut state = self.state.borrow_mut();
+            let len = state.edit.text().chars().count();
+            let pos = state.edit.hover_text(pt, &self.rect(&Point::origin()), ctx.draw_ctx).unwrap_or(len);
+            state.edit.set_cursor_pos(pos);
+        }
+        let resp = just_status(WidgetStatus::REDRAW).msg(self.blink.start());
+        Some(if focus { resp.msg(self.open()) } else { resp })
+    }
+    fn key_down(&mut self, kc: &Keycode, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        self.select_time?;
+        self.blink.reset();
+        let mut state = self.state.borrow_mut();
+        match *kc {
+            Keycode::Up => state.move_highlight(-1),
+            Keycode::Down => state.move_highlight(1),
+            Keycode::Return | Keycode::Tab => {
+                let changed = state.pick();
+                return Some(text_changed(changed))
+            }
+            Keycode::Escape => {
+                drop(state);
+                return self.deselect()
+            }
+            _ => {
+                if state.edit.key_down(kc, &ctx.draw_ctx.render_text) {
+                    state.refresh();
+                    return Some(text_changed(true))
+                }
+            }
+        }
+        Some(just_status(WidgetStatus::REDRAW))
+    }
+    fn deselect(&mut self) -> Option<WidgetResponse> {
+        self.select_time?;
+        self.select_time = None;
+        self.blink.stop();
+        let mut state = self.state.borrow_mut();
+        let text = state.edit.text();
+        let changed = state.set_symbol(&text);
+        Some(text_changed(changed).msg(Message::ClosePopup(state.popup)))
+    }
+    fn serialize(&self, buf: &mut MDDoc) {
+        let state = self.state.borrow();
+        let ticker = state.edit.text().trim().to_uppercase();
+        if self.describe {
+            if let Some(info) = state.watchlist.get(&ticker) {
+                buf.title.name = Some(info.name.clone()).filter(|n| !n.is_empty());
+                buf.title.sector = Some(info.sector.clone()).filter(|s| !s.is_empty());
+            }
+        }
+        buf.body.extend_from_slice(ticker.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields() {
+        assert_eq!(split_csv_line("AMD, Advanced Micro Devices ,Technology\r"), vec!["AMD", "Advanced Micro Devices", "Technology"]);
+        assert_eq!(split_csv_line("AMZN,\"Amazon.com, Inc.\",Retail"), vec!["AMZN", "Amazon.com, Inc.", "Retail"]);
+        assert_eq!(split_csv_line("X,\"say \"\"hi\"\"\","), vec!["X", "say \"hi\"", ""]);
+        assert_eq!(split_csv_line(""), vec![""]);
+    }
+
+    fn tickers(w: &Watchlist, text: &str) -> Vec<String> {
+        w.search(text).into_iter().map(|i| w.symbols[i].ticker.clone()).collect()
+    }
+
+    #[test]
+    fn parse_skips_header_and_blank_lines() {
+        let w = Watchlist::parse("Ticker,Name,Sector\n\namd,Advanced Micro Devices,Technology\nAAPL,Apple\n");
+        assert_eq!(w.symbols.len(), 2);
+        assert_eq!(w.symbols[0].ticker, "AMD");
+        assert_eq!(w.symbols[1].sector, "");
+        assert_eq!(w.get("aapl").map(|s| s.name.as_str()), Some("Apple"));
+    }
+
+    #[test]
+    fn search_tickers_before_names() {
+        let w = Watchlist::parse("AMD,Advanced Micro Devices\nAMZN,Amazon\nSAM,Boston Beer\nMU,Micron\nAMAT,Applied Materials");
+        assert_eq!(tickers(&w, "am"), vec!["AMD", "AMZN", "AMAT"]);
+        assert_eq!(tickers(&w, " mic"), vec!["AMD", "MU"]);
+        assert_eq!(tickers(&w, "mu"), vec!["MU"]);
+        assert!(tickers(&w, "zzz").is_empty());
+    }
+
+    #[test]
+    fn search_is_capped() {
+        let csv: String = (0..20).map(|i| format!("A{}\n", i)).collect();
+        assert_eq!(Watchlist::parse(&csv).search("A").len(), MAX_SUGGESTIONS);
+    }
+}
@@ -0,0 +1,388 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::{SystemTime};
+use sdl2::mouse::SystemCursor;
+use sdl2::keyboard::Keycode;
+use crate::primitives::{DrawCtx, Point, Rect, RotateRect, Radians, Radii, Paint, DrawRoundRect, InBounds};
+use crate::render_text::{TextParams};
+use crate::textedit::{TextEdit, Blink, get_char_from_keycode};
+use crate::overlay::{Popup, PopupId, Anchor, new_anchor};
+use crate::suggest::{Suggest, SuggestionList};
+use crate::interface::{EventCtx};
+use crate::events::{MouseEvent};
+use crate::widgets::{Widget, WidgetResponse, WidgetStatus, Message, just_status, MDDoc};
+
+//suggestions shown below the field at once
+const MAX_SUGGESTIONS: usize = 8;
+//space between tags and between rows of tags
+const TAG_GAP: f32 = 4.;
+//the text field after the tags wraps to a new row when narrower than this many chars
+const MIN_INPUT_CHARS: usize = 6;
+
+//shared by the field and its suggestion list in the overlay
+struct TagState {
+    vocabulary: Vec<String>,
+    tags: Vec<String>,
+    edit: TextEdit,
+    //vocabulary entries not picked yet that contain the typed text
+    matches: Vec<String>,
+    highlight: Option<usize>,
+    item_size: Point,
+    popup: PopupId,
+}
+
+impl TagState {
+    fn new(vocabulary: Vec<String>) -> Self {
+        TagState { vocabulary, tags: Vec::new(), edit: TextEdit::new("", Point::origin()), matches: Vec::new(), highlight: None,
+            item_size: Point::origin(), popup: PopupId::new() }
+    }
+    fn refresh(&mut self) {
+        let text = self.edit.text().trim().to_lowercase();
+        let tags = &self.tags;
+        self.matches = self.vocabulary.iter()
+            .filter(|v| v.to_lowercase().contains(&text) && !tags.iter().any(|t| t.eq_ignore_ascii_case(v)))
+            .take(MAX_SUGGESTIONS)
+            .cloned()
+            .collect();
+        self.highlight = self.highlight.filter(|h| *h < self.matches.len());
+    }
+    //values from the vocabulary keep its spelling so "pennant" is stored as "Pennant"
+    fn add(&mut self, text: &str) -> bool {
+        let text = text.trim();
+        if text.is_empty() || self.tags.iter().any(|t| t.eq_ignore_ascii_case(text)) {
+            return false
+        }
+        let tag = self.vocabulary.iter().find(|v| v.eq_ignore_ascii_case(text)).cloned().unwrap_or(text.to_string());
+        self.tags.push(tag);
+        true
+    }
+    //adds the highlighted suggestion, or the typed text if none is highlighted
+    fn commit(&mut self) -> bool {
+        let text = match self.highlight.and_then(|h| self.matches.get(h)) {
+            Some(m) => m.clone(),
+            None => self.edit.text()
+        };
+        let added = self.add(&text);
+        self.edit.set_text("");
+        self.highlight = None;
+        self.refresh();
+        added
+    }
+    fn move_highlight(&mut self, delta: i32) {
+        if self.matches.is_empty() {
+            return
+        }
+        let last = self.matches.len() as i32 - 1;
+        self.highlight = Some(match self.highlight {
+            Some(h) => (h as i32 + delta).max(0).min(last) as usize,
+            None => if delta > 0 { 0 } else { last as usize }
+        });
+    }
+}
+
+fn changed() -> WidgetResponse {
+    WidgetResponse::new(WidgetStatus::REMEASURE).msg(Message::Changed)
+}
+
+impl Suggest for TagState {
+    fn rows(&self) -> usize {
+        self.matches.len()
+    }
+    fn item_size(&self) -> Point {
+        self.item_size
+    }
+    fn highlight(&self) -> Option<usize> {
+        self.highlight
+    }
+    fn set_highlight(&mut self, row: Option<usize>) {
+        self.highlight = row;
+    }
+    fn draw_row(&self, row: usize, r: Rect, ctx: &DrawCtx) {
+        let pad = Point::new(ctx.theme.padding.x, 0.);
+        ctx.draw_text(&self.matches[row], Rect::new(r.c1 + pad, r.c2), &TextParams::new());
+    }
+    //several values can be picked in a row
+    fn pick_row(&mut self, _: usize) -> WidgetResponse {
+        if self.commit() { changed() } else { just_status(WidgetStatus::FINE) }
+    }
+}
+
+//picks any number of values from a vocabulary, or new ones typed in.
+//Return or a comma adds the typed text, Backspace on an empty field removes the last tag
+//and clicking the x on a tag removes it
+pub struct TagInput {
+    state: Rc<RefCell<TagState>>,
+    suggestions: Rc<RefCell<SuggestionList<TagState>>>,
+    anchor: Anchor,
+    select_time: Option<SystemTime>,
+    blink: Blink,
+    natural_width: f32,
+    width: f32,
+    //where each tag is drawn, followed by the text field
+    tag_rects: Vec<Rect>,
+    input: Rect,
+    size: Point,
+}
+
+#[allow(dead_code)]
+impl TagInput {
+    pub fn new<T: Into<String>>(vocabulary: Vec<T>, num_chars: usize, ctx: &DrawCtx) -> Self {
+        let width = ctx.render_text.measure(&"A".repeat(num_chars.max(MIN_INPUT_CHARS)), 1.).x;
+        let mut w = TagInput::with_state(TagState::new(vocabulary.into_iter().map(|v| v.into()).collect()), width);
+        w.layout(ctx);
+        w
+    }
+    //not laid out yet
+    fn with_state(state: TagState, width: f32) -> Self {
+        let state = Rc::new(RefCell::new(state));
+        let suggestions = Rc::new(RefCell::new(SuggestionList(state.clone())));
+        TagInput { state, suggestions, anchor: new_anchor(), select_time: None, blink: Blink::new(), natural_width: width, width,
+            tag_rects: Vec::new(), input: Rect::empty(), size: Point::origin() }
+    }
+    pub fn tags(&self) -> Vec<String> {
+        self.state.borrow().tags.clone()
+    }
+    pub fn set_tags<T: AsRef<str>>(mut self, tags: Vec<T>, ctx: &DrawCtx) -> Self {
+        {
+            let mut state = self.state.borrow_mut();
+            state.tags.clear();
+            tags.iter().for_each(|t| { state.add(t.as_ref()); });
+        }
+        self.layout(ctx);
+        self
+    }
+    //tags fill rows left to right and wrap at the width of the field
+    fn layout(&mut self, ctx: &DrawCtx) {
+        let mut state = self.state.borrow_mut();
+        let pad = ctx.theme.padding.x;
+        let h = ctx.render_text.measure("A", 1.).y;
+        let right = self.width - pad;
+        let mut pos = Point::new(pad, 0.);
+        let mut place = |w: f32| {
+            if pos.x + w > right && pos.x > pad {
+                pos = Point::new(pad, pos.y + h + TAG_GAP);
+            }
+            let r = Rect::new(pos, pos + Point::new(w, h));
+            pos.x += w + TAG_GAP;
+            r
+        };
+        self.tag_rects = state.tags.iter().map(|t| place(ctx.render_text.measure(t, 1.).x + pad * 2. + h)).collect();
+        let min_input = ctx.render_text.measure(&"A".repeat(MIN_INPUT_CHARS), 1.).x;
+        let r = place(min_input);
+        self.input = Rect::new(r.c1, Point::new(right.max(r.c2.x), r.c2.y));
+        self.size = Point::new(self.width, self.input.c2.y);
+        state.edit.set_size(Point::new(self.input.width(), h));
+        state.item_size = Point::new(self.width, h);
+    }
+    //the x that removes a tag is a square at its right end
+    fn remove_rect(&self, i: usize) -> Rect {
+        let r = &self.tag_rects[i];
+        Rect::new(Point::new(r.c2.x - r.height(), r.c1.y), r.c2)
+    }
+    fn remove_at(&self, pt: &Point) -> Option<usize> {
+        (0..self.tag_rects.len()).find(|i| self.remove_rect(*i).in_bounds(pt, &Point::origin()))
+    }
+    fn input_rect(&self, off: &Point) -> RotateRect {
+        RotateRect::from_rect(Rect::new(*off + self.input.c1, *off + self.input.c2), Radians(0.))
+    }
+    fn open(&self) -> Message {
+        let widget: Rc<RefCell<dyn Widget>> = self.suggestions.clone();
+        let popup = self.state.borrow().popup;
+        Message::OpenPopup(Popup::new(popup, widget, Rc::clone(&self.anchor), Point::new(0., 2.)))
+    }
+}
+
+pub fn new_tag_input(vocabulary: Vec<&str>, num_chars: usize, ctx: &DrawCtx) -> Box<dyn Widget> {
+    Box::new(TagInput::new(vocabulary, num_chars, ctx))
+}
+
+impl Widget for TagInput {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        self.size
+    }
+    fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
+        self.layout(ctx);
+        self.size
+    }
+    //extra width is used for more tags per row
+    fn arrange(&mut self, size: &Point, ctx: &DrawCtx) {
+        self.width = size.x.max(self.natural_width);
+        self.layout(ctx);
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        Some(ctx.render_text.baseline(1.))
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        self.anchor.set(*off + Point::new(0., self.size.y));
+        let theme = &ctx.theme;
+        let (border_width, border_color) = match self.select_time {
+            Some(_) => (theme.focus_width, theme.palette.focus),
+            None => (theme.border_width, theme.palette.border)
+        };
+        let rect = RotateRect::from_rect(Rect::new(*off, *off + self.size), Radians(0.));
+        DrawRoundRect::new(rect, Paint::Solid(theme.palette.widget_bg))
+            .border(border_width, border_color)
+            .draw(ctx);
+        let state = self.state.borrow();
+        let pad = Point::new(theme.padding.x, 0.);
+        for (i, tag) in state.tags.iter().enumerate() {
+            let r = &self.tag_rects[i];
+            let r = Rect::new(*off + r.c1, *off + r.c2);
+            ctx.draw_round_rect(r.clone(), Radii::all(theme.radius), Paint::Solid(theme.palette.hover), None);
+            ctx.draw_text(tag, Rect::new(r.c1 + pad, r.c2), &TextParams::new());
+            let x = self.remove_rect(i);
+            let (c, arm) = (*off + (x.c1 + x.c2) / 2., x.height() * 0.2);
+            ctx.draw_line(c - Point::new(arm, arm), c + Point::new(arm, arm), theme.palette.text, 2.);
+            ctx.draw_line(c + Point::new(-arm, arm), c + Point::new(arm, -arm), theme.palette.text, 2.);
+        }
+        let input = self.input_rect(off);
+        ctx.push_clip_rotated(&input);
+        state.edit.draw(&input, self.select_time.is_some() && self.blink.visible(), ctx);
+        ctx.pop_clip();
+    }
+    //the first press focuses the field and opens the suggestions
+    fn mouse_event(&mut self, ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        match *ev {
+            MouseEvent::Move => {
+                *ctx.cursor = if self.remove_at(pt).is_some() { SystemCursor::Hand } else { SystemCursor::IBeam };
+                return Some(just_status(WidgetStatus::FINE))
+            }
+            MouseEvent::Press { .. } => (),
+            _ => return None
+        }
+        let focus = self.select_time.is_none();
+        self.select_time = Some(SystemTime::now());
+        let mut resp = match self.remove_at(pt) {
+            Some(i) => {
+                let mut state = self.state.borrow_mut();
+                state.tags.remove(i);
+                state.refresh();
+                changed()
+            }
+            None => {
+                let mut state = self.state.borrow_mut();
+                let len = state.edit.text().chars().count();
+                let pos = state.edit.hover_text(pt, &self.input_rect(&Point::origin()), ctx.draw_ctx).unwrap_or(len);
+                state.edit.set_cursor_pos(pos);
+                just_status(WidgetStatus::REDRAW)
+            }
+        };
+        if focus {
+            self.state.borrow_mut().refresh();
+            resp = resp.msg(self.open());
+        }
+        Some(resp.msg(self.blink.start()))
+    }
+    fn key_down(&mut self, kc: &Keycode, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        self.select_time?;
+        self.blink.reset();
+        let mut state = self.state.borrow_mut();
+        let add = get_char_from_keycode(*kc) == Some(',') || *kc == Keycode::Return;
+        if add {
+            return Some(if state.commit() { changed() } else { just_status(WidgetStatus::REDRAW) })
+        }
+        match *kc {
+            Keycode::Up => state.move_highlight(-1),
+            Keycode::Down => state.move_highlight(1),
+            Keycode::Escape => {
+                state.edit.set_text("");
+                drop(state);
+                return self.deselect()
+            }
+            Keycode::Backspace if state.edit.text().is_empty() => {
+                if state.tags.pop().is_none() {
+                    return Some(just_status(WidgetStatus::FINE))
+                }
+                state.refresh();
+                return Some(changed())
+            }
+            _ => {
+                if state.edit.key_down(kc, &ctx.draw_ctx.render_text) {
+                    state.highlight = None;
+                    state.refresh();
+                }
+            }
+        }
+        Some(just_status(WidgetStatus::REDRAW))
+    }
+    //typed text that wasn't added yet becomes a tag when the field loses focus
+    fn deselect(&mut self) -> Option<WidgetResponse> {
+        self.select_time?;
+        self.select_time = None;
+        self.blink.stop();
+        let mut state = self.state.borrow_mut();
+        state.highlight = None;
+        let resp = if state.commit() { changed() } else { just_status(WidgetStatus::REDRAW) };
+        Some(resp.msg(Message::ClosePopup(state.popup)))
+    }
+    //the tags go to the front matter as they are, the body gets them as a comma list
+    fn serialize(&self, buf: &mut MDDoc) {
+        let state = self.state.borrow();
+        buf.patterns = state.tags.clone();
+        buf.body.extend_from_slice(state.tags.join(", ").as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(vocabulary: &[&str]) -> TagState {
+        TagState::new(vocabulary.iter().map(|v| v.to_string()).collect())
+    }
+
+    fn typed(state: &mut TagState, text: &str) {
+        state.edit.set_text(text);
+        state.refresh();
+    }
+
+    #[test]
+    fn add_keeps_the_vocabulary_spelling() {
+        let mut s = state(&["Pennant", "Bull Flag"]);
+        assert!(s.add(" pennant "));
+        assert!(s.add("cup"));
+        assert!(!s.add("PENNANT"));
+        assert!(!s.add("Cup"));
+        assert!(!s.add("  "));
+        assert_eq!(s.tags, vec!["Pennant", "cup"]);
+    }
+
+    #[test]
+    fn matches_hide_picked_tags() {
+        let mut s = state(&["Bull Flag", "Bear Flag", "Pennant"]);
+        typed(&mut s, "FLAG");
+        assert_eq!(s.matches, vec!["Bull Flag", "Bear Flag"]);
+        s.add("bull flag");
+        s.refresh();
+        assert_eq!(s.matches, vec!["Bear Flag"]);
+    }
+
+    #[test]
+    fn commit_takes_the_highlight_or_the_typed_text() {
+        let mut s = state(&["Bull Flag", "Bear Flag"]);
+        typed(&mut s, "flag");
+        s.move_highlight(1);
+        s.move_highlight(1);
+        assert!(s.commit());
+        assert_eq!(s.tags, vec!["Bear Flag"]);
+        assert_eq!(s.edit.text(), "");
+        assert_eq!(s.highlight, None);
+        assert_eq!(s.matches, vec!["Bull Flag"]);
+        typed(&mut s, "wedge");
+        assert!(s.commit());
+        assert!(!s.commit());
+        assert_eq!(s.tags, vec!["Bear Flag", "wedge"]);
+    }
+
+    #[test]
+    fn serialize_fills_patterns_and_body() {
+        let mut s = state(&["Pennant"]);
+        s.add("pennant");
+        s.add("cup");
+        let w = TagInput::with_state(s, 100.);
+        let mut buf = MDDoc::empty();
+        w.serialize(&mut buf);
+        assert_eq!(buf.patterns, vec!["Pennant", "cup"]);
+        assert_eq!(buf.body, b"Pennant, cup");
+    }
+}
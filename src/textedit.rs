@@ -2,13 +2,15 @@ extern crate ropey;
 extern crate sdl2;
 
 use ropey::Rope;
-use std::time::{SystemTime};
+use std::time::{Duration, SystemTime, Instant};
 use sdl2::mouse::SystemCursor;
-use crate::primitives::{Point, RotateRect, DrawCtx, rgb_to_f32, Rect, Radians};
+use crate::primitives::{Point, RotateRect, DrawCtx, Rect, Radians, DrawRoundRect, Paint};
 use crate::render_text::{RenderText, TextParams};
-use crate::interface::{EventCtx, AppState, HandleKey};
-use crate::widgets::{Widget, WidgetResponse, WidgetStatus, just_status, MDDoc};
-use std::cell::RefCell;
+use crate::interface::{EventCtx};
+use crate::events::{MouseEvent};
+use crate::widgets::{Widget, WidgetResponse, WidgetStatus, Message, just_status, MDDoc};
+use crate::timer::{Timer};
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use sdl2::keyboard::Keycode;
 
@@ -36,8 +38,9 @@ pub struct TextEdit {
     text_params: TextParams,
 }
 
+#[allow(dead_code)]
 impl TextEdit {
-    fn new(text: &str, size: Point) -> Self {
+    pub fn new(text: &str, size: Point) -> Self {
         TextEdit {
             text_rope: Rope::from_str(text),
             top_line: 0,
@@ -102,6 +105,21 @@ impl TextEdit {
     pub fn set_cursor_pos(&mut self, cursor_idx: usize) {
         self.cursor.char_idx = std::cmp::max(0, std::cmp::min(self.text_rope.len_chars(), cursor_idx));
     }
+    pub fn cursor_pos(&self) -> usize {
+        self.cursor.char_idx
+    }
+    pub fn text(&self) -> String {
+        self.text_rope.to_string()
+    }
+    //replaces the text and puts the cursor at the end
+    pub fn set_text(&mut self, text: &str) {
+        self.text_rope = Rope::from_str(text);
+        self.top_line = 0;
+        self.cursor.char_idx = self.text_rope.len_chars();
+    }
+    pub fn set_size(&mut self, size: Point) {
+        self.size = size;
+    }
     pub fn move_cursor(&mut self, dir: TextCursorDirection) {
         let cursor_line = self.text_rope.char_to_line(self.cursor.char_idx);
         let line = self.text_rope.line(cursor_line);
@@ -192,7 +210,8 @@ impl TextEdit {
             }
         }
     }
-    pub fn draw(&self, rect: &RotateRect, select_time: &Option<SystemTime>, draw_ctx: &DrawCtx) {
+    //the cursor is only drawn when show_cursor is set, see Blink
+    pub fn draw(&self, rect: &RotateRect, show_cursor: bool, draw_ctx: &DrawCtx) {
         let cursor_line = self.text_rope.char_to_line(self.cursor.char_idx);
         let rt = &draw_ctx.render_text;
         let line_height = rt.line_height(self.text_params.scale);
@@ -204,9 +223,7 @@ impl TextEdit {
             let end_idx = self.text_rope.line_to_char(self.top_line + max_lines);
             rt.draw(self.text_rope.slice(start_idx..end_idx).as_str().unwrap(), &self.text_params, &rect, draw_ctx)
         }
-        if let Some(_) = select_time {
-            //let millis = select_time.elapsed().unwrap().as_millis() % 1000;
-            //if millis < 500 {
+        if show_cursor {
             let before_str = self.text_rope.slice(self.text_rope.line_to_char(cursor_line)..self.cursor.char_idx).as_str().unwrap();
             let mut cursor_pt1 = Point::new(
                 rt.measure(before_str, self.text_params.scale).x / rect.size.x, 
@@ -214,32 +231,75 @@ impl TextEdit {
             let mut cursor_pt2 = Point::new(cursor_pt1.x, cursor_pt1.y + line_height / rect.size.y);
             cursor_pt1 = rect.transform(&draw_ctx.viewport).model_to_pixel(&cursor_pt1.to_vec4());
             cursor_pt2 = rect.transform(&draw_ctx.viewport).model_to_pixel(&cursor_pt2.to_vec4());
-            draw_ctx.draw_line(cursor_pt1, cursor_pt2, rgb_to_f32(0, 0, 0), 3.);
-            //}
+            draw_ctx.draw_line(cursor_pt1, cursor_pt2, draw_ctx.theme.palette.text, 3.);
         }
     }
 }
 
-impl HandleKey for TextEdit {
-    fn handle_key_down(&mut self, kc: &Keycode, ctx: &EventCtx) -> Option<WidgetResponse> {
-        let rt = &ctx.draw_ctx.render_text;
+//one on and off cycle of the cursor
+const BLINK: Duration = Duration::from_millis(1000);
+
+//blinks the cursor of a focused text field with a timer that redraws every half cycle.
+//Each start replaces the timer, the old one stops itself the next time it fires
+pub struct Blink {
+    running: Rc<Cell<bool>>,
+    since: Instant
+}
+
+impl Blink {
+    pub fn new() -> Self {
+        Blink { running: Rc::new(Cell::new(false)), since: Instant::now() }
+    }
+    //the message starting the timer, sent along with the response to the focusing click
+    pub fn start(&mut self) -> Message {
+        self.stop();
+        let running = Rc::new(Cell::new(true));
+        self.running = running.clone();
+        self.since = Instant::now();
+        Message::StartTimer(Timer::every(BLINK / 2, move |_| {
+            if running.get() { Some(just_status(WidgetStatus::REDRAW)) } else { None }
+        }))
+    }
+    pub fn stop(&mut self) {
+        self.running.set(false);
+    }
+    //shows the cursor again, called on every key so it doesn't blink away while typing
+    pub fn reset(&mut self) {
+        self.since = Instant::now();
+    }
+    //the cursor stays on while no timer is running, e.g. for fields focused when created
+    pub fn visible(&self) -> bool {
+        let millis = self.since.elapsed().as_millis();
+        !self.running.get() || millis % BLINK.as_millis() < BLINK.as_millis() / 2
+    }
+}
+
+impl TextEdit {
+    //returns true if the text changed
+    pub fn key_down(&mut self, kc: &Keycode, rt: &RenderText) -> bool {
         if let Some(ch) = get_char_from_keycode(*kc) {
             self.insert_char(ch, rt);
+            true
         }
         else if let Some(dir) = get_dir_from_keycode(*kc) {
             self.move_cursor(dir);
+            false
         }
         else if *kc == Keycode::Backspace {
             self.delete_char(rt);
+            true
         }
-        Some(just_status(WidgetStatus::REDRAW))
+        else { false }
     }
 }
 
 pub struct TextBox {
     text_edit: Rc<RefCell<TextEdit>>,
     select_time: Option<SystemTime>,
-    rect: RotateRect
+    blink: Blink,
+    rect: RotateRect,
+    //size before any stretching by the parent
+    natural_size: Point
 }
 
 impl TextBox {
@@ -247,56 +307,101 @@ impl TextBox {
         TextBox {
             text_edit: Rc::new(RefCell::new(TextEdit::new(default_text, size))),
             select_time: None,
-            rect: RotateRect::from_rect(Rect{ c1: Point::origin(), c2: size }, Radians(0.))
+            blink: Blink::new(),
+            rect: RotateRect::from_rect(Rect{ c1: Point::origin(), c2: size }, Radians(0.)),
+            natural_size: size
         }
     }
     pub fn new_rotated(default_text: &str, rect: RotateRect) -> Self {
         TextBox {
             text_edit: Rc::new(RefCell::new(TextEdit::new(default_text, rect.size))),
             select_time: None,
+            blink: Blink::new(),
+            natural_size: rect.size,
             rect
         }
     }
+    //starts out with the cursor in it
+    pub fn focused(mut self) -> Self {
+        self.select_time = Some(SystemTime::now());
+        let len = self.text_edit.borrow().text_rope.len_chars();
+        self.text_edit.borrow_mut().set_cursor_pos(len);
+        self
+    }
+    fn set_size(&mut self, size: Point) {
+        self.rect.size = size;
+        self.text_edit.borrow_mut().size = size;
+    }
 }
 
 #[allow(dead_code)]
 impl Widget for TextBox {
     fn draw(&self, offset: &Point, draw_ctx: &DrawCtx) {
         let rect = RotateRect { offset: *offset, ..self.rect.clone() };
-        rect.builder().color(255, 255, 255).get().draw(draw_ctx);
-        self.text_edit.borrow().draw(&rect, &self.select_time, draw_ctx);
+        let theme = &draw_ctx.theme;
+        let (border_width, border_color) = match self.select_time {
+            Some(_) => (theme.focus_width, theme.palette.focus),
+            None => (theme.border_width, theme.palette.border)
+        };
+        DrawRoundRect::new(rect.clone(), Paint::Solid(theme.palette.widget_bg))
+            .border(border_width, border_color)
+            .draw(draw_ctx);
+        draw_ctx.push_clip_rotated(&rect);
+        self.text_edit.borrow().draw(&rect, self.select_time.is_some() && self.blink.visible(), draw_ctx);
+        draw_ctx.pop_clip();
     }
     fn measure(&self, _: &DrawCtx) -> Point {
         self.rect.size
     }
-    fn hover(&mut self, _: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        *ctx.cursor = SystemCursor::IBeam;
-        Some(just_status(WidgetStatus::FINE))
+    fn remeasure(&mut self, _: &DrawCtx) -> Point {
+        let size = self.natural_size;
+        self.set_size(size);
+        size
+    }
+    fn arrange(&mut self, size: &Point, _: &DrawCtx) {
+        let size = Point::new(size.x.max(self.natural_size.x), size.y.max(self.natural_size.y));
+        self.set_size(size);
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        Some(ctx.render_text.baseline(self.text_edit.borrow().text_params.scale))
     }
     fn serialize(&self, buf: &mut MDDoc) {
         let rope = &self.text_edit.borrow().text_rope;
         let s = rope.slice(0..rope.len_chars()).as_str().unwrap();
         buf.body.extend_from_slice(s.as_bytes())
     }
-    fn click(&mut self, off: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        {
-            let mut text_edit = self.text_edit.borrow_mut();
-            let cursor_pos = text_edit.hover_text(off, &self.rect, &ctx.draw_ctx).unwrap_or(0);
-            self.select_time = Some(SystemTime::now());
-            text_edit.set_cursor_pos(cursor_pos);
-            *ctx.cursor = SystemCursor::IBeam;
+    fn mouse_event(&mut self, ev: &MouseEvent, off: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        match *ev {
+            MouseEvent::Move => {
+                *ctx.cursor = SystemCursor::IBeam;
+                Some(just_status(WidgetStatus::FINE))
+            }
+            MouseEvent::Press { .. } => {
+                {
+                    let mut text_edit = self.text_edit.borrow_mut();
+                    let cursor_pos = text_edit.hover_text(off, &self.rect, &ctx.draw_ctx).unwrap_or(0);
+                    self.select_time = Some(SystemTime::now());
+                    text_edit.set_cursor_pos(cursor_pos);
+                    *ctx.cursor = SystemCursor::IBeam;
+                }
+                Some(just_status(WidgetStatus::REDRAW).msg(self.blink.start()))
+            }
+            _ => None
         }
-        let text_edit = self.text_edit.clone();
-        Some((WidgetStatus::REDRAW, Rc::new(move |app: &mut AppState| {
-            app.key_item = Some(text_edit.clone());
-        })))
+    }
+    //a selected text box takes every key
+    fn key_down(&mut self, kc: &Keycode, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        self.select_time?;
+        self.blink.reset();
+        let changed = self.text_edit.borrow_mut().key_down(kc, &ctx.draw_ctx.render_text);
+        let resp = just_status(WidgetStatus::REDRAW);
+        Some(if changed { resp.msg(Message::Changed) } else { resp })
     }
     fn deselect(&mut self) -> Option<WidgetResponse> { 
         if self.select_time.is_some() {
             self.select_time = None;
-            Some((WidgetStatus::REDRAW, Rc::new(move |app: &mut AppState| {
-                app.key_item = None;
-            })))
+            self.blink.stop();
+            Some(just_status(WidgetStatus::REDRAW))
         }
         else { None }
     }
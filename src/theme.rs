@@ -0,0 +1,294 @@
+extern crate nalgebra_glm;
+
+use nalgebra_glm as glm;
+use std::fs;
+use crate::primitives::{Point, Shadow, rgb_to_f32};
+
+//selects a built-in theme by name or loads a theme file, e.g. MD_UI_THEME=dark
+const THEME_VAR: &str = "MD_UI_THEME";
+//every glyph gets its own texture at this size times the ui scale
+const MAX_FONT_SIZE: f32 = 200.;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ThemeColor {
+    Background,
+    Text,
+    WidgetBg,
+    Border,
+    Hover,
+    Accent,
+    AccentText,
+    Focus,
+    Arrow,
+    Shadow,
+    Success,
+    Warning,
+    Error
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Palette {
+    pub background: glm::Vec4,
+    pub text: glm::Vec4,
+    pub widget_bg: glm::Vec4,
+    pub border: glm::Vec4,
+    pub hover: glm::Vec4,
+    pub accent: glm::Vec4,
+    pub accent_text: glm::Vec4,
+    pub focus: glm::Vec4,
+    pub arrow: glm::Vec4,
+    pub shadow: glm::Vec4,
+    //notification colors, see notify::Severity
+    pub success: glm::Vec4,
+    pub warning: glm::Vec4,
+    pub error: glm::Vec4
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub palette: Palette,
+    //logical pixel size glyphs are rasterized at, TextParams::scale is relative to this
+    pub font_size: f32,
+    pub padding: Point,
+    pub spacing: Point,
+    pub border_width: f32,
+    pub focus_width: f32,
+    pub radius: f32,
+    pub scrollbar_width: f32
+}
+
+#[allow(dead_code)]
+impl Theme {
+    pub fn light() -> Self {
+        Theme {
+            name: String::from("light"),
+            palette: Palette {
+                background: rgb_to_f32(128, 128, 128),
+                text: rgb_to_f32(0, 0, 0),
+                widget_bg: rgb_to_f32(255, 255, 255),
+                border: rgb_to_f32(160, 160, 160),
+                hover: rgb_to_f32(168, 238, 240),
+                accent: rgb_to_f32(0, 255, 255),
+                accent_text: rgb_to_f32(0, 0, 0),
+                focus: rgb_to_f32(0, 120, 215),
+                arrow: rgb_to_f32(0, 0, 255),
+                shadow: glm::vec4(0., 0., 0., 0.3),
+                success: rgb_to_f32(40, 160, 70),
+                warning: rgb_to_f32(230, 160, 0),
+                error: rgb_to_f32(210, 40, 40)
+            },
+            font_size: 24.,
+            padding: Point::new(5., 5.),
+            spacing: Point::new(10., 10.),
+            border_width: 1.,
+            focus_width: 2.,
+            radius: 3.,
+            scrollbar_width: 8.
+        }
+    }
+    pub fn dark() -> Self {
+        Theme {
+            name: String::from("dark"),
+            palette: Palette {
+                background: rgb_to_f32(30, 30, 30),
+                text: rgb_to_f32(220, 220, 220),
+                widget_bg: rgb_to_f32(50, 50, 52),
+                border: rgb_to_f32(90, 90, 95),
+                hover: rgb_to_f32(38, 79, 120),
+                accent: rgb_to_f32(0, 120, 215),
+                accent_text: rgb_to_f32(255, 255, 255),
+                focus: rgb_to_f32(86, 156, 214),
+                arrow: rgb_to_f32(200, 200, 200),
+                shadow: glm::vec4(0., 0., 0., 0.6),
+                success: rgb_to_f32(80, 190, 100),
+                warning: rgb_to_f32(240, 180, 40),
+                error: rgb_to_f32(240, 80, 80)
+            },
+            ..Theme::light()
+        }
+    }
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(Theme::light()),
+            "dark" => Some(Theme::dark()),
+            _ => None
+        }
+    }
+    //theme files are `key = value` lines, lines starting with # are comments.
+    //`base = dark` resets to a built-in theme so it should come first,
+    //colors are #rrggbb or #rrggbbaa and points are `x y`
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut theme = Theme::light();
+        for (n, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut kv = line.splitn(2, '=');
+            let (key, val) = match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) => (k.trim(), v.trim()),
+                _ => return Err(format!("line {}: expected `key = value`", n + 1))
+            };
+            theme.set(key, val).map_err(|e| format!("line {}: {}", n + 1, e))?;
+        }
+        Ok(theme)
+    }
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let src = fs::read_to_string(path).map_err(|e| format!("Could not read theme {}: {:?}", path, e))?;
+        Theme::parse(&src).map_err(|e| format!("Error in theme {}: {}", path, e))
+    }
+    //the theme named by MD_UI_THEME, the light theme when it isn't set
+    pub fn load() -> Result<Self, String> {
+        match std::env::var(THEME_VAR) {
+            Ok(name) => Theme::builtin(&name).map(Ok).unwrap_or_else(|| Theme::from_file(&name)),
+            Err(_) => Ok(Theme::light())
+        }
+    }
+    fn set(&mut self, key: &str, val: &str) -> Result<(), String> {
+        match key {
+            "base" => *self = Theme::builtin(val).ok_or(format!("unknown base theme `{}`", val))?,
+            "name" => self.name = val.to_string(),
+            "background" => self.palette.background = parse_color(val)?,
+            "text" => self.palette.text = parse_color(val)?,
+            "widget_bg" => self.palette.widget_bg = parse_color(val)?,
+            "border" => self.palette.border = parse_color(val)?,
+            "hover" => self.palette.hover = parse_color(val)?,
+            "accent" => self.palette.accent = parse_color(val)?,
+            "accent_text" => self.palette.accent_text = parse_color(val)?,
+            "focus" => self.palette.focus = parse_color(val)?,
+            "arrow" => self.palette.arrow = parse_color(val)?,
+            "shadow" => self.palette.shadow = parse_color(val)?,
+            "success" => self.palette.success = parse_color(val)?,
+            "warning" => self.palette.warning = parse_color(val)?,
+            "error" => self.palette.error = parse_color(val)?,
+            "font_size" => self.font_size = parse_font_size(val)?,
+            "padding" => self.padding = parse_point(val)?,
+            "spacing" => self.spacing = parse_point(val)?,
+            "border_width" => self.border_width = parse_size(val)?,
+            "focus_width" => self.focus_width = parse_size(val)?,
+            "radius" => self.radius = parse_size(val)?,
+            "scrollbar_width" => self.scrollbar_width = parse_size(val)?,
+            _ => return Err(format!("unknown key `{}`", key))
+        }
+        Ok(())
+    }
+    pub fn color(&self, c: ThemeColor) -> glm::Vec4 {
+        let p = &self.palette;
+        match c {
+            ThemeColor::Background => p.background,
+            ThemeColor::Text => p.text,
+            ThemeColor::WidgetBg => p.widget_bg,
+            ThemeColor::Border => p.border,
+            ThemeColor::Hover => p.hover,
+            ThemeColor::Accent => p.accent,
+            ThemeColor::AccentText => p.accent_text,
+            ThemeColor::Focus => p.focus,
+            ThemeColor::Arrow => p.arrow,
+            ThemeColor::Shadow => p.shadow,
+            ThemeColor::Success => p.success,
+            ThemeColor::Warning => p.warning,
+            ThemeColor::Error => p.error
+        }
+    }
+    pub fn shadow(&self, offset: f32, blur: f32) -> Shadow {
+        Shadow::new(Point::new(0., offset), blur, self.palette.shadow)
+    }
+}
+
+fn parse_f32(val: &str) -> Result<f32, String> {
+    match val.parse::<f32>() {
+        Ok(f) if f.is_finite() => Ok(f),
+        _ => Err(format!("expected a number, got `{}`", val))
+    }
+}
+
+//widths, radii and padding, a negative one would turn rects inside out
+fn parse_size(val: &str) -> Result<f32, String> {
+    let f = parse_f32(val)?;
+    if f < 0. {
+        return Err(format!("expected a size of 0 or more, got `{}`", val));
+    }
+    Ok(f)
+}
+
+fn parse_font_size(val: &str) -> Result<f32, String> {
+    let f = parse_f32(val)?;
+    if f <= 0. || f > MAX_FONT_SIZE {
+        return Err(format!("expected a font size between 0 and {}, got `{}`", MAX_FONT_SIZE, val));
+    }
+    Ok(f)
+}
+
+fn parse_point(val: &str) -> Result<Point, String> {
+    let v = val.split_whitespace().map(parse_size).collect::<Result<Vec<f32>, String>>()?;
+    match v.as_slice() {
+        [x] => Ok(Point::new(*x, *x)),
+        [x, y] => Ok(Point::new(*x, *y)),
+        _ => Err(format!("expected `x y`, got `{}`", val))
+    }
+}
+
+fn parse_color(val: &str) -> Result<glm::Vec4, String> {
+    let hex = val.trim_start_matches('#');
+    let err = || format!("expected #rrggbb or #rrggbbaa, got `{}`", val);
+    //the byte slicing below needs every char to be one byte
+    if !val.starts_with('#') || !hex.is_ascii() || (hex.len() != 6 && hex.len() != 8) {
+        return Err(err());
+    }
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err());
+    let mut c = rgb_to_f32(byte(0)?, byte(2)?, byte(4)?);
+    if hex.len() == 8 {
+        c[3] = byte(6)? as f32 / 255.;
+    }
+    Ok(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#ff8000"), Ok(rgb_to_f32(255, 128, 0)));
+        assert_eq!(parse_color("#00000080").map(|c| c[3]), Ok(128. / 255.));
+        assert!(parse_color("ff8000").is_err());
+        assert!(parse_color("#ff80").is_err());
+        assert!(parse_color("#gg8000").is_err());
+    }
+
+    #[test]
+    fn non_ascii_colors_are_rejected() {
+        //6 bytes but not 6 chars, slicing it by byte would panic
+        assert!(parse_color("#ééé").is_err());
+        assert!(parse_color("#f€ff").is_err());
+    }
+
+    #[test]
+    fn parse_theme_file() {
+        let src = "# my theme\nbase = dark\n\nname = mine\ntext = #102030\npadding = 4 6\nradius = 2.5\n";
+        let theme = Theme::parse(src).unwrap();
+        assert_eq!(theme.name, "mine");
+        assert_eq!(theme.palette.text, rgb_to_f32(16, 32, 48));
+        assert_eq!(theme.palette.background, Theme::dark().palette.background);
+        assert_eq!(theme.padding, Point::new(4., 6.));
+        assert_eq!(theme.spacing, Theme::light().spacing);
+        assert_eq!(theme.radius, 2.5);
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        assert_eq!(Theme::parse("name = a\nborder #fff").err(), Some(String::from("line 2: expected `key = value`")));
+        assert!(Theme::parse("colour = #ffffff").unwrap_err().contains("unknown key"));
+        assert!(Theme::parse("base = solarized").unwrap_err().contains("unknown base theme"));
+        assert!(Theme::parse("padding = 1 2 3").is_err());
+        assert!(Theme::parse("radius = wide").is_err());
+        assert_eq!(Theme::parse("name = a\nfont_size = 0").err(),
+            Some(String::from("line 2: expected a font size between 0 and 200, got `0`")));
+        assert!(Theme::parse("font_size = 1e6").is_err());
+        assert!(Theme::parse("font_size = inf").is_err());
+        assert!(Theme::parse("radius = NaN").is_err());
+        assert!(Theme::parse("border_width = -1").unwrap_err().starts_with("line 1:"));
+        assert!(Theme::parse("padding = 5 -5").is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use crate::widgets::{WidgetResponse};
+
+//time between the steps of an animation, the event loop never wakes up more often than this
+pub const FRAME: Duration = Duration::from_millis(1000 / 120);
+
+static NEXT_TIMER_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TimerId(usize);
+
+impl TimerId {
+    pub fn new() -> Self {
+        TimerId(NEXT_TIMER_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+//gets how far along an animation is from 0 to 1, other timers always get 1.
+//The response is handled like one from a widget, None stops a repeating timer
+pub type TimerFn = Rc<RefCell<dyn FnMut(f32) -> Option<WidgetResponse>>>;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Repeat {
+    Once,
+    Every(Duration),
+    Animate(Duration)
+}
+
+//widgets start timers by sending Message::StartTimer, see AppState::start_timer
+#[derive(Clone)]
+pub struct Timer {
+    id: TimerId,
+    repeat: Repeat,
+    start: Instant,
+    due: Instant,
+    callback: TimerFn
+}
+
+#[allow(dead_code)]
+impl Timer {
+    fn new<F>(delay: Duration, repeat: Repeat, f: F) -> Self where F: FnMut(f32) -> Option<WidgetResponse> + 'static {
+        let start = Instant::now();
+        Timer { id: TimerId::new(), repeat, start, due: start + delay, callback: Rc::new(RefCell::new(f)) }
+    }
+    pub fn once<F>(delay: Duration, f: F) -> Self where F: FnMut(f32) -> Option<WidgetResponse> + 'static {
+        Timer::new(delay, Repeat::Once, f)
+    }
+    //intervals shorter than a frame run once a frame, a zero interval would always be due and spin the event loop
+    pub fn every<F>(interval: Duration, f: F) -> Self where F: FnMut(f32) -> Option<WidgetResponse> + 'static {
+        let interval = interval.max(FRAME);
+        Timer::new(interval, Repeat::Every(interval), f)
+    }
+    //runs every frame for the duration, the last call always gets 1
+    pub fn animate<F>(duration: Duration, f: F) -> Self where F: FnMut(f32) -> Option<WidgetResponse> + 'static {
+        Timer::new(FRAME, Repeat::Animate(duration), f)
+    }
+    pub fn id(&self) -> TimerId {
+        self.id
+    }
+    fn progress(&self, now: Instant) -> f32 {
+        match self.repeat {
+            Repeat::Animate(d) if d > Duration::from_secs(0) => {
+                let elapsed = now.saturating_duration_since(self.start);
+                (elapsed.as_secs_f32() / d.as_secs_f32()).min(1.)
+            }
+            _ => 1.
+        }
+    }
+}
+
+//value between from and to for an animation's progress, eased in and out
+pub fn ease(from: f32, to: f32, t: f32) -> f32 {
+    let t = t.max(0.).min(1.);
+    from + (to - from) * t * t * (3. - 2. * t)
+}
+
+pub struct Timers {
+    timers: Vec<Timer>
+}
+
+#[allow(dead_code)]
+impl Timers {
+    pub fn new() -> Self {
+        Timers { timers: Vec::new() }
+    }
+    pub fn start(&mut self, timer: Timer) -> TimerId {
+        let id = timer.id;
+        self.timers.push(timer);
+        id
+    }
+    //true if the timer was still running
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let n = self.timers.len();
+        self.timers.retain(|t| t.id != id);
+        n != self.timers.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+    pub fn next_due(&self) -> Option<Instant> {
+        self.timers.iter().map(|t| t.due).min()
+    }
+    //calls every timer that is due and reschedules the repeating ones,
+    //returning the responses for the AppState to handle
+    pub fn fire(&mut self) -> Vec<WidgetResponse> {
+        let now = Instant::now();
+        let (due, rest): (Vec<Timer>, Vec<Timer>) = self.timers.drain(..).partition(|t| t.due <= now);
+        self.timers = rest;
+        let mut resps = Vec::new();
+        for mut t in due {
+            let progress = t.progress(now);
+            let resp = (&mut *t.callback.borrow_mut())(progress);
+            let again = match t.repeat {
+                Repeat::Once => false,
+                //stays on the original schedule unless it fell a whole interval behind
+                Repeat::Every(interval) => {
+                    t.due += interval;
+                    if t.due <= now {
+                        t.due = now + interval;
+                    }
+                    true
+                }
+                Repeat::Animate(_) => {
+                    t.due = now + FRAME;
+                    progress < 1.
+                }
+            };
+            if again && resp.is_some() {
+                self.timers.push(t);
+            }
+            resps.extend(resp);
+        }
+        resps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use crate::widgets::{WidgetStatus, just_status};
+
+    fn counter() -> (Rc<Cell<u32>>, impl FnMut(f32) -> Option<WidgetResponse>) {
+        let calls = Rc::new(Cell::new(0));
+        let c = calls.clone();
+        (calls, move |_| {
+            c.set(c.get() + 1);
+            Some(just_status(WidgetStatus::REDRAW))
+        })
+    }
+
+    //moves the timer's schedule back so it is due now
+    fn overdue(mut t: Timer, by: Duration) -> Timer {
+        t.start -= by;
+        t.due -= by;
+        t
+    }
+
+    #[test]
+    fn not_fired_early() {
+        let (calls, f) = counter();
+        let mut timers = Timers::new();
+        timers.start(Timer::once(Duration::from_secs(60), f));
+        assert!(timers.fire().is_empty());
+        assert_eq!(calls.get(), 0);
+        assert!(timers.next_due().unwrap() > Instant::now());
+    }
+
+    #[test]
+    fn once_fires_once() {
+        let (calls, f) = counter();
+        let mut timers = Timers::new();
+        timers.start(overdue(Timer::once(Duration::from_secs(1), f), Duration::from_secs(1)));
+        assert_eq!(timers.fire().len(), 1);
+        assert!(timers.is_empty());
+        assert!(timers.fire().is_empty());
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn every_keeps_its_schedule() {
+        let interval = Duration::from_secs(10);
+        let (calls, f) = counter();
+        let mut timers = Timers::new();
+        let t = overdue(Timer::every(interval, f), interval + Duration::from_millis(1));
+        let due = t.due;
+        timers.start(t);
+        timers.fire();
+        assert_eq!(calls.get(), 1);
+        assert_eq!(timers.next_due(), Some(due + interval));
+    }
+
+    #[test]
+    fn every_skips_missed_intervals() {
+        let interval = Duration::from_secs(1);
+        let (calls, f) = counter();
+        let mut timers = Timers::new();
+        timers.start(overdue(Timer::every(interval, f), Duration::from_secs(5)));
+        let before = Instant::now();
+        assert_eq!(timers.fire().len(), 1);
+        assert_eq!(calls.get(), 1);
+        let due = timers.next_due().unwrap();
+        assert!(due >= before + interval && due <= Instant::now() + interval);
+    }
+
+    #[test]
+    fn every_runs_at_most_once_a_frame() {
+        let (calls, f) = counter();
+        let mut timers = Timers::new();
+        let t = Timer::every(Duration::from_secs(0), f);
+        assert_eq!(t.repeat, Repeat::Every(FRAME));
+        timers.start(overdue(t, FRAME));
+        assert_eq!(timers.fire().len(), 1);
+        assert_eq!(calls.get(), 1);
+        assert!(timers.next_due().unwrap() > Instant::now());
+    }
+
+    #[test]
+    fn every_stops_on_none() {
+        let mut timers = Timers::new();
+        timers.start(overdue(Timer::every(Duration::from_secs(1), |_| None), Duration::from_secs(1)));
+        assert!(timers.fire().is_empty());
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn animation_ends_at_one() {
+        let progress = Rc::new(Cell::new(0.));
+        let p = progress.clone();
+        let mut timers = Timers::new();
+        let t = Timer::animate(Duration::from_secs(10), move |t| {
+            p.set(t);
+            Some(just_status(WidgetStatus::REDRAW))
+        });
+        timers.start(overdue(t, Duration::from_secs(5)));
+        timers.fire();
+        assert!(progress.get() >= 0.5 && progress.get() < 1.);
+        assert!(!timers.is_empty());
+        let t = timers.timers.pop().unwrap();
+        timers.start(overdue(t, Duration::from_secs(10)));
+        timers.fire();
+        assert_eq!(progress.get(), 1.);
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn cancel() {
+        let (calls, f) = counter();
+        let mut timers = Timers::new();
+        let id = timers.start(overdue(Timer::once(Duration::from_secs(1), f), Duration::from_secs(1)));
+        assert!(timers.cancel(id));
+        assert!(!timers.cancel(id));
+        assert!(timers.fire().is_empty());
+        assert_eq!(calls.get(), 0);
+    }
+
+    #[test]
+    fn ease_ends() {
+        assert_eq!(ease(2., 4., 0.), 2.);
+        assert_eq!(ease(2., 4., 0.5), 3.);
+        assert_eq!(ease(2., 4., 1.), 4.);
+        assert_eq!(ease(2., 4., 7.), 4.);
+    }
+}
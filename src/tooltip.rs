@@ -0,0 +1,247 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+use crate::primitives::{DrawCtx, Point, Rect, Radii, Border, Paint};
+use crate::render_text::{TextParams};
+use crate::overlay::{Overlay, Popup, PopupId, new_anchor};
+use crate::widgets::{Widget, Wrapper, WrapperT};
+
+//how long the mouse has to rest on a widget before its tooltip shows
+const TOOLTIP_DELAY: Duration = Duration::from_millis(600);
+//tooltip text wraps at this many chars
+const TOOLTIP_CHARS: usize = 40;
+//distance from the mouse to the top left of the tooltip
+const TOOLTIP_OFFSET: (f32, f32) = (12., 20.);
+
+//gives the widget a tooltip, see Tooltips
+pub struct Tooltip {
+    text: String
+}
+
+pub fn with_tooltip(widget: Box<dyn Widget>, text: &str) -> Box<dyn Widget> {
+    Box::new(Wrapper::new(Tooltip { text: text.to_string() }, widget))
+}
+
+impl WrapperT for Tooltip {
+    fn tooltip(&self) -> Option<&str> {
+        Some(&self.text)
+    }
+}
+
+//the tooltip of the deepest widget on the path that has one
+pub fn tooltip_at(root: &dyn Widget, path: &[usize]) -> Option<String> {
+    let mut w = root;
+    let mut text = w.tooltip().map(String::from);
+    for i in path {
+        w = match w.child(*i) {
+            Some(c) => c,
+            None => break
+        };
+        if let Some(t) = w.tooltip() {
+            text = Some(t.to_string());
+        }
+    }
+    text
+}
+
+//breaks the text into lines no wider than width, words longer than a line get a line of their own
+pub fn wrap(text: &str, width: f32, ctx: &DrawCtx) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if ctx.render_text.measure(&format!("{} {}", line, word), 1.).x <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string())
+        }
+    }
+    lines
+}
+
+//the box drawn in the overlay as a passive popup so it never hides what is under it
+struct TooltipBox {
+    lines: Vec<String>,
+    size: Point
+}
+
+impl TooltipBox {
+    fn new(text: &str, ctx: &DrawCtx) -> Self {
+        let lines = wrap(text, ctx.render_text.measure(&"a".repeat(TOOLTIP_CHARS), 1.).x, ctx);
+        let pad = ctx.theme.padding;
+        let width = lines.iter().fold(0f32, |w, l| w.max(ctx.render_text.measure(l, 1.).x));
+        let height = ctx.render_text.line_height(1.) * lines.len() as f32;
+        TooltipBox { lines, size: Point::new(width, height) + pad * 2. }
+    }
+}
+
+impl Widget for TooltipBox {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        self.size
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        let theme = &ctx.theme;
+        let radii = Radii::all(theme.radius);
+        let r = Rect::new(*off, *off + self.size);
+        let border = Border::new(Point::new(theme.border_width, theme.border_width), theme.palette.border);
+        ctx.draw_shadow(r.clone(), radii, &theme.shadow(2., 3.));
+        ctx.draw_round_rect(r.clone(), radii, Paint::Solid(theme.palette.widget_bg), Some(&border));
+        let h = ctx.render_text.line_height(1.);
+        for (i, line) in self.lines.iter().enumerate() {
+            let c1 = *off + theme.padding + Point::new(0., i as f32 * h);
+            ctx.draw_text(line, Rect::new(c1, Point::new(r.c2.x - theme.padding.x, c1.y + h)), &TextParams::new());
+        }
+    }
+}
+
+//shows the tooltip of the widget under the mouse once the mouse has rested on it.
+//Leaving the widget or clicking hides it, after a click it stays hidden until the mouse moves to another widget
+pub struct Tooltips {
+    text: Option<String>,
+    since: Instant,
+    shown: bool,
+    dismissed: bool,
+    popup: PopupId
+}
+
+impl Tooltips {
+    pub fn new() -> Self {
+        Tooltips { text: None, since: Instant::now(), shown: false, dismissed: false, popup: PopupId::new() }
+    }
+    //called with the tooltip under the mouse whenever it moves, true if a tooltip was hidden
+    pub fn hover(&mut self, text: Option<String>, overlay: &mut Overlay) -> bool {
+        if text == self.text {
+            return false
+        }
+        self.text = text;
+        self.since = Instant::now();
+        self.dismissed = false;
+        self.hide(overlay)
+    }
+    pub fn click(&mut self, overlay: &mut Overlay) -> bool {
+        self.dismissed = true;
+        self.hide(overlay)
+    }
+    pub fn hide(&mut self, overlay: &mut Overlay) -> bool {
+        if !self.shown {
+            return false
+        }
+        self.shown = false;
+        overlay.close(self.popup);
+        true
+    }
+    //when the tooltip under the mouse is due to open
+    pub fn next_due(&self) -> Option<Instant> {
+        if self.shown || self.dismissed || self.text.is_none() { None } else { Some(self.since + TOOLTIP_DELAY) }
+    }
+    //the text to show once the mouse has rested long enough
+    fn due(&self) -> Option<&str> {
+        if self.shown || self.dismissed || self.since.elapsed() < TOOLTIP_DELAY { None } else { self.text.as_deref() }
+    }
+    //opens the tooltip once the delay has passed, true if it did
+    pub fn tick(&mut self, mouse: &Point, overlay: &mut Overlay, ctx: &DrawCtx) -> bool {
+        let tip = match self.due() {
+            Some(text) => TooltipBox::new(text, ctx),
+            None => return false
+        };
+        //kept inside the window, flipped above the mouse near the bottom
+        let mut pos = *mouse + Point::new(TOOLTIP_OFFSET.0, TOOLTIP_OFFSET.1);
+        if pos.y + tip.size.y > ctx.viewport.y {
+            pos.y = mouse.y - TOOLTIP_OFFSET.1 - tip.size.y;
+        }
+        pos.x = pos.x.min(ctx.viewport.x - tip.size.x).max(0.);
+        let anchor = new_anchor();
+        anchor.set(pos);
+        let widget: Rc<RefCell<dyn Widget>> = Rc::new(RefCell::new(tip));
+        overlay.open(Popup::new(self.popup, widget, anchor, Point::origin()).passive());
+        self.shown = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Blank;
+
+    impl Widget for Blank {
+        fn draw(&self, _: &Point, _: &DrawCtx) { }
+    }
+
+    //as if the mouse had rested on the widget for the whole delay
+    fn overdue(t: &mut Tooltips) {
+        t.since -= TOOLTIP_DELAY;
+    }
+
+    //as if tick had opened the tooltip
+    fn show(t: &mut Tooltips, overlay: &mut Overlay) {
+        let widget: Rc<RefCell<dyn Widget>> = Rc::new(RefCell::new(Blank));
+        overlay.open(Popup::new(t.popup, widget, new_anchor(), Point::origin()).passive());
+        t.shown = true;
+    }
+
+    #[test]
+    fn shows_after_the_delay() {
+        let mut overlay = Overlay::new();
+        let mut t = Tooltips::new();
+        assert_eq!(t.next_due(), None);
+        assert!(!t.hover(Some(String::from("tip")), &mut overlay));
+        assert_eq!(t.next_due(), Some(t.since + TOOLTIP_DELAY));
+        assert_eq!(t.due(), None);
+        overdue(&mut t);
+        assert_eq!(t.due(), Some("tip"));
+        //moving on the same widget doesn't restart the delay
+        t.hover(Some(String::from("tip")), &mut overlay);
+        assert_eq!(t.due(), Some("tip"));
+        show(&mut t, &mut overlay);
+        assert_eq!(t.due(), None);
+        assert_eq!(t.next_due(), None);
+    }
+
+    #[test]
+    fn click_dismisses_until_another_widget() {
+        let mut overlay = Overlay::new();
+        let mut t = Tooltips::new();
+        t.hover(Some(String::from("a")), &mut overlay);
+        show(&mut t, &mut overlay);
+        assert!(t.click(&mut overlay));
+        assert!(overlay.is_empty());
+        overdue(&mut t);
+        assert_eq!(t.due(), None);
+        assert_eq!(t.next_due(), None);
+        t.hover(Some(String::from("a")), &mut overlay);
+        assert_eq!(t.next_due(), None);
+        t.hover(Some(String::from("b")), &mut overlay);
+        assert!(t.next_due().is_some());
+        overdue(&mut t);
+        assert_eq!(t.due(), Some("b"));
+    }
+
+    #[test]
+    fn leaving_hides_and_rearms() {
+        let mut overlay = Overlay::new();
+        let mut t = Tooltips::new();
+        t.hover(Some(String::from("a")), &mut overlay);
+        show(&mut t, &mut overlay);
+        assert!(t.hover(None, &mut overlay));
+        assert!(overlay.is_empty());
+        assert_eq!(t.next_due(), None);
+        t.hover(Some(String::from("a")), &mut overlay);
+        assert_eq!(t.due(), None);
+        overdue(&mut t);
+        assert_eq!(t.due(), Some("a"));
+    }
+
+    #[test]
+    fn deepest_tooltip_wins() {
+        let inner = with_tooltip(with_tooltip(Box::new(Blank), "inner"), "middle");
+        let root = with_tooltip(Box::new(Wrapper::new(Tooltip { text: String::from("outer") }, inner)), "root");
+        assert_eq!(tooltip_at(root.as_ref(), &[]), Some(String::from("root")));
+        assert_eq!(tooltip_at(root.as_ref(), &[0, 0]), Some(String::from("middle")));
+        assert_eq!(tooltip_at(root.as_ref(), &[0, 0, 0, 0]), Some(String::from("inner")));
+        //a path past the end of the tree keeps what was found so far
+        assert_eq!(tooltip_at(root.as_ref(), &[0, 3]), Some(String::from("outer")));
+        assert_eq!(tooltip_at(&Blank, &[0]), None);
+    }
+}
@@ -1,11 +1,20 @@
 extern crate nalgebra_glm;
 extern crate bitflags;
 
-use crate::interface::{CallbackFn, EventCtx, AppState};
+use crate::interface::{EventCtx};
 use crate::render_text::{TextParams};
-use crate::textedit::{TextBox};
-use crate::primitives::{DrawCtx, Point, Rect, RotateRect, Radians, Border, BorderRect, InBounds, rgb_to_f32};
+use crate::textedit::{TextBox, get_char_from_keycode};
+use crate::primitives::{DrawCtx, Point, Rect, RotateRect, Radians, Border, BorderRect, InBounds,
+    Radii, Paint, lighten};
+use crate::theme::{ThemeColor};
+use crate::overlay::{Popup, PopupId, Anchor, new_anchor};
+use crate::layout::{LayoutParams, Align, CellInfo, row_extent, place, distribute};
+use crate::events::{MouseEvent};
+use crate::dialog::{Answer};
+use crate::notify::{Severity};
+use crate::timer::{Timer, TimerId};
 use sdl2::mouse::SystemCursor;
+use sdl2::keyboard::Keycode;
 use nalgebra_glm as glm;
 use bitflags::bitflags;
 use std::rc::Rc;
@@ -15,24 +24,29 @@ use chrono::Datelike;
 pub struct MDTitle {
     pub symbol: String,
     pub strategy: String,
-    pub date: String
+    pub date: String,
+    //from the watchlist, see symbols::SymbolInput
+    pub name: Option<String>,
+    pub sector: Option<String>
 }
 
 impl MDTitle {
     pub fn empty() -> Self {
-        MDTitle { symbol: String::new(), strategy: String::new(), date: String::new() }
+        MDTitle { symbol: String::new(), strategy: String::new(), date: String::new(), name: None, sector: None }
     }
 }
 
 pub struct MDDoc {
     pub title: MDTitle,
     pub portfolio: String,
+    //from the pattern field, see tags::TagInput
+    pub patterns: Vec<String>,
     pub body: Vec<u8>
 }
 
 impl MDDoc {
     pub fn empty() -> Self {
-        MDDoc { title: MDTitle::empty(), portfolio: String::new(), body: Vec::new() }
+        MDDoc { title: MDTitle::empty(), portfolio: String::new(), patterns: Vec::new(), body: Vec::new() }
     }
 }
 
@@ -47,22 +61,83 @@ pub trait Widget {
     fn measure(&self, _: &DrawCtx) -> Point {
         Point::origin()
     }
-    fn hover(&mut self, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+    fn deselect(&mut self) -> Option<WidgetResponse> { 
         None
     }
-    fn click(&mut self, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+    //delta is in wheel notches, positive y scrolls the content up
+    fn scroll(&mut self, _: &Point, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
         None
     }
-    fn deselect(&mut self) -> Option<WidgetResponse> { 
+    //keys that aren't taken by the focused text item
+    fn key_down(&mut self, _: &Keycode, _: &mut EventCtx) -> Option<WidgetResponse> {
         None
     }
     fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
         self.measure(ctx)
     }
+    fn layout_params(&self) -> Option<&LayoutParams> {
+        None
+    }
+    //distance from the top of the widget to the baseline of its first line of text
+    fn baseline(&self, _: &DrawCtx) -> Option<f32> {
+        None
+    }
+    //called by containers that give a widget more room than it measured, e.g. to stretch it.
+    //measure should include the extra room until the next remeasure
+    fn arrange(&mut self, _: &Point, _: &DrawCtx) { }
+    //index of the child under the point, the MouseTracker follows these down
+    //to find the path of widgets the mouse is over
+    fn child_at(&self, _: &Point, _: &DrawCtx) -> Option<usize> {
+        None
+    }
+    //position of a child relative to this widget
+    fn child_offset(&self, _: usize) -> Point {
+        Point::origin()
+    }
+    fn child(&self, _: usize) -> Option<&dyn Widget> {
+        None
+    }
+    fn child_mut(&mut self, _: usize) -> Option<&mut dyn Widget> {
+        None
+    }
+    //sent by the MouseTracker, pt is relative to the widget. Returning None passes
+    //the event on to the parent
+    fn mouse_event(&mut self, _: &MouseEvent, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+        None
+    }
+    //every ancestor of the target sees a mouse event here first, outermost first.
+    //Returning Some stops the event before it reaches the target
+    fn capture(&mut self, _: &MouseEvent, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+        None
+    }
+    //called with the response of child idx on its way up the tree, parents can react to
+    //the messages of their children and remove the ones they handle
+    fn child_response(&mut self, _: usize, resp: WidgetResponse, _: &mut EventCtx) -> WidgetResponse {
+        resp
+    }
+    //stores the widget's value where other widgets can read it, see form::Field
+    fn publish(&self) { }
+    //hidden widgets take no space in their container, aren't drawn and aren't serialized
+    fn visible(&self) -> bool { true }
+    //text shown near the mouse after it rests on the widget, see tooltip::Tooltips
+    fn tooltip(&self) -> Option<&str> { None }
     fn do_serialize(&self) -> bool { true }
     fn serialize(&self, _: &mut MDDoc) { }
 }
 
+//visits a widget and everything below it, parents first
+pub fn walk(w: &dyn Widget, f: &mut dyn FnMut(&dyn Widget)) {
+    f(w);
+    if !w.visible() {
+        return
+    }
+    let mut i = 0;
+    while let Some(c) = w.child(i) {
+        walk(c, f);
+        i += 1;
+    }
+}
+
 bitflags! {
     pub struct WidgetStatus: u32 {
         const FINE = 0;
@@ -71,34 +146,59 @@ bitflags! {
     }
 }
 
-pub type WidgetResponse = (WidgetStatus, CallbackFn);
+//what a widget wants done outside of itself. Messages pass every ancestor's
+//child_response on the way up and the ones left at the root are handled by the AppState
+#[derive(Clone)]
+pub enum Message {
+    OpenPopup(Popup),
+    ClosePopup(PopupId),
+    //the user edited a value
+    Changed,
+    //write the form out
+    Submit,
+    //sent by the buttons of a dialog, the dialog turns it into DialogClosed
+    Answer(Answer),
+    DialogClosed(PopupId, Answer),
+    //shows a toast and sets the status bar, see AppState::notify
+    Notify(Severity, String),
+    StartTimer(Timer),
+    CancelTimer(TimerId)
+}
 
-pub fn no_cb() -> CallbackFn {
-    Rc::new(|_: &mut AppState| {})
+pub struct WidgetResponse {
+    pub status: WidgetStatus,
+    pub msgs: Vec<Message>
 }
-pub fn just_cb(cb: CallbackFn) -> WidgetResponse {
-    (WidgetStatus::FINE, cb)
+
+impl WidgetResponse {
+    pub fn new(status: WidgetStatus) -> Self {
+        WidgetResponse { status, msgs: Vec::new() }
+    }
+    pub fn msg(mut self, msg: Message) -> Self {
+        self.msgs.push(msg);
+        self
+    }
+}
+
+pub fn just_msg(msg: Message) -> WidgetResponse {
+    WidgetResponse::new(WidgetStatus::FINE).msg(msg)
 }
 pub fn just_status(status: WidgetStatus) -> WidgetResponse {
-    (status, no_cb())
+    WidgetResponse::new(status)
 }
 
-pub fn combine_response(r1: &WidgetResponse, r2: &WidgetResponse) -> WidgetResponse {
-    let cb1 = Rc::clone(&r1.1);
-    let cb2 = Rc::clone(&r2.1);
-    (r1.0 | r2.0, 
-      Rc::new(move |app: &mut AppState| {
-          (cb1)(app);
-          (cb2)(app);
-      })
-    )
+pub fn combine_response(mut r1: WidgetResponse, r2: WidgetResponse) -> WidgetResponse {
+    r1.status |= r2.status;
+    r1.msgs.extend(r2.msgs);
+    r1
 }
 
-/*pub fn combine_response_opt(r1: &Option<WidgetResponse>, r2: &Option<WidgetResponse>) {
-    match r1 {
-        Some(w) => 
+pub fn combine_response_opt(r1: Option<WidgetResponse>, r2: Option<WidgetResponse>) -> Option<WidgetResponse> {
+    match (r1, r2) {
+        (Some(r1), Some(r2)) => Some(combine_response(r1, r2)),
+        (r1, r2) => r1.or(r2)
     }
-}*/
+}
 
 pub struct WidgetList {
     pub orientation: Orientation,
@@ -106,13 +206,21 @@ pub struct WidgetList {
     pub size: Point,
     widgets: Vec<Box<dyn Widget>>,
     widget_rects: Vec<Rect>,
-    needs_draw: RefCell<Vec<bool>>
+    needs_draw: RefCell<Vec<bool>>,
+    cell_params: LayoutParams,
+    //size given by the parent through arrange, reset on remeasure
+    min_size: Point
 }
 
 impl WidgetList {
     pub fn new(orientation: Orientation, spacing: u32) -> Self {
         WidgetList { orientation, spacing, widgets: Vec::new(), widget_rects: Vec::new(), needs_draw: RefCell::new(Vec::new()), 
-                     size: Point::origin() }
+                     size: Point::origin(), cell_params: LayoutParams::default(), min_size: Point::origin() }
+    }
+    //default placement for children that don't have their own LayoutParams
+    pub fn align(mut self, h_align: Align, v_align: Align) -> Self {
+        self.cell_params = self.cell_params.align(h_align, v_align);
+        self
     }
     
     pub fn get_widget(&self, idx: usize) -> Option<&Box<dyn Widget>> {
@@ -124,56 +232,80 @@ impl WidgetList {
     pub fn get_idx(&self, off_pt: &Point, ctx: &DrawCtx) -> Option<usize> {
         self.widget_rects.iter().position(|r| r.in_bounds(off_pt, &ctx.viewport)) 
     }
+    fn main_axis(&self, p: &Point) -> f32 {
+        match self.orientation { Orientation::Vertical => p.y, Orientation::Horizontal => p.x }
+    }
+    fn cross_axis(&self, p: &Point) -> f32 {
+        match self.orientation { Orientation::Vertical => p.x, Orientation::Horizontal => p.y }
+    }
+    fn from_axes(&self, main: f32, cross: f32) -> Point {
+        match self.orientation { Orientation::Vertical => Point::new(cross, main), Orientation::Horizontal => Point::new(main, cross) }
+    }
+    //stacks the children along the main axis, sharing any space beyond their
+    //natural size between the children that grow
+    fn layout(&mut self, ctx: &DrawCtx) {
+        let cells: Vec<CellInfo> = self.widgets.iter().map(|w| CellInfo::new(w, &self.cell_params, ctx)).collect();
+        let n_visible = cells.iter().filter(|c| c.visible).count();
+        let spacing = self.spacing as f32 * (n_visible.max(1) - 1) as f32;
+        let natural_main = cells.iter().map(|c| self.main_axis(&c.size)).sum::<f32>() + spacing;
+        let (natural_cross, baseline) = match self.orientation {
+            Orientation::Horizontal => row_extent(&cells.iter().collect::<Vec<_>>()),
+            Orientation::Vertical => (cells.iter().map(|c| c.size.x).fold(0., f32::max), 0.)
+        };
+        let main = natural_main.max(self.main_axis(&self.min_size));
+        let cross = natural_cross.max(self.cross_axis(&self.min_size));
+        let grow: Vec<f32> = cells.iter().map(|c| c.params.grow).collect();
+        let extra = distribute(main - natural_main, &grow);
+        let mut pos = 0.;
+        let mut rects = Vec::new();
+        for (i, c) in cells.iter().enumerate() {
+            let len = self.main_axis(&c.size) + extra[i];
+            let c1 = self.from_axes(pos, 0.);
+            let cell = Rect::new(c1, c1 + self.from_axes(len, cross));
+            let mut info = CellInfo { size: self.from_axes(len, self.cross_axis(&c.size)), baseline: c.baseline, params: c.params.clone(), visible: c.visible };
+            if extra[i] > 0. {
+                match self.orientation {
+                    Orientation::Horizontal => info.params.h_align = Align::Stretch,
+                    Orientation::Vertical => info.params.v_align = Align::Stretch,
+                }
+            }
+            rects.push(place(&info, &cell, baseline));
+            if c.visible {
+                pos += len + self.spacing as f32;
+            }
+        }
+        for (i, r) in rects.iter().enumerate() {
+            let size = Point::new(r.width(), r.height());
+            if cells[i].visible && size != cells[i].size {
+                self.widgets[i].arrange(&size, ctx);
+            }
+        }
+        self.widget_rects = rects;
+        *self.needs_draw.borrow_mut() = vec![true; self.widgets.len()];
+        self.size = self.from_axes(main, cross);
+    }
 }
 
 impl WidgetIterT for WidgetList {
     type Child = Box<dyn Widget>;
     fn add(&mut self, item: Self::Child, ctx: &DrawCtx) {
-        let m = item.measure(ctx);
-        let spacing = if self.widgets.is_empty() { 0. } else { self.spacing as f32 };
         self.widgets.push(item);
-        let mut c1 = Point::origin();
-        match self.orientation {
-            Orientation::Vertical => {
-                c1.y = self.size.y + spacing;
-                self.size.x = self.size.x.max(m.x);
-                self.size.y += spacing + m.y;
-            }
-            _ => {
-                c1.x = self.size.x + spacing;
-                self.size.x += spacing + m.x;
-                self.size.y = self.size.y.max(m.y);
-            }
-        };
-        self.needs_draw.borrow_mut().push(true);
-        self.widget_rects.push(Rect {c1, c2: c1 + m});
+        self.layout(ctx);
     }
     fn measure_items(&self, _: &DrawCtx) -> Point {
         self.size
     }
     fn remeasure_items(&mut self, ctx: &DrawCtx) -> Point {
-        let mut off = Point::origin();
-        let mut size = Point::origin();
-        for (i, w) in self.widgets.iter_mut().enumerate() {
-            let spacing = if i == 0 { 0. } else { self.spacing as f32 };
-            let m = w.remeasure(ctx);
-            match self.orientation {
-                Orientation::Vertical => {
-                    off.y = size.y + spacing;
-                    size.x = size.x.max(m.x);
-                    size.y += m.y + spacing;
-                }
-                _ => {
-                    off.x = size.x + spacing;
-                    size.x += m.x + spacing;
-                    size.y = size.y.max(m.y);
-                }
-            }
-            self.widget_rects[i] = Rect{ c1: off, c2: off + m };
+        for w in self.widgets.iter_mut() {
+            w.remeasure(ctx);
         }
-        *self.needs_draw.borrow_mut() = vec![true; self.widgets.len()];
-        self.size = size;
-        size
+        self.min_size = Point::origin();
+        self.layout(ctx);
+        self.size
+    }
+    fn arrange_items(&mut self, size: &Point, ctx: &DrawCtx) {
+        self.min_size = *size;
+        self.layout(ctx);
     }
     fn widgets_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item=&'a mut Box<dyn Widget>> + 'a> {
         Box::new(self.widgets.iter_mut())
@@ -188,7 +320,7 @@ impl WidgetIterT for WidgetList {
         match self.orientation {
             Orientation::Vertical => {
                 for w in &self.widgets {
-                    if w.do_serialize() {
+                    if w.do_serialize() && w.visible() {
                         buf.body.push('*' as u8);
                         buf.body.push(' ' as u8);
                         w.serialize(buf);
@@ -198,7 +330,7 @@ impl WidgetIterT for WidgetList {
             }
             Orientation::Horizontal => {
                 for w in &self.widgets {
-                    if w.do_serialize() {
+                    if w.do_serialize() && w.visible() {
                         w.serialize(buf);
                         buf.body.push(' ' as u8);
                     }
@@ -218,17 +350,16 @@ pub trait WidgetIterT {
     fn remeasure_items(&mut self, ctx: &DrawCtx) -> Point;
     fn serialize_items(&self, buf: &mut MDDoc);
     fn deselect_self(&mut self) -> Option<WidgetResponse> { None }
-    fn click_self(&mut self, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> { None }
-    fn hover_self(&mut self, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> { None }
+    fn mouse_event_self(&mut self, _: &MouseEvent, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> { None }
+    fn capture_self(&mut self, _: &MouseEvent, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> { None }
     fn draw_self(&self, _: &Point, _: &DrawCtx) { }
+    fn arrange_items(&mut self, _: &Point, _: &DrawCtx) { }
+    fn params(&self) -> Option<&LayoutParams> { None }
     fn builder<'a>(self, ctx: &'a DrawCtx) -> WidgetBuilder<'a, Self> where Self: std::marker::Sized + 'static {
         WidgetBuilder::new(self, ctx)
     }
-    fn handle_response(&mut self, resp: Option<(usize, WidgetResponse)>) -> Option<WidgetResponse> {
-        if let Some(_) = resp {
-            return Some(resp.unwrap().1)
-        }
-        None
+    fn handle_response(&mut self, _: usize, resp: WidgetResponse, _: &mut EventCtx) -> WidgetResponse {
+        resp
     }
 }
 
@@ -267,63 +398,27 @@ impl<'a, C, T: WidgetIterT<Child = C>> std::ops::AddAssign<C> for WidgetBuilder<
 impl<T: WidgetIterT> Widget for T {
     fn draw(&self, offset: &Point, ctx: &DrawCtx) {
         self.draw_self(offset, ctx);
-        for (w, r) in self.widgets_plus_rects() {
+        ctx.push_clip(&Rect::new(*offset, *offset + self.measure_items(ctx)));
+        for (w, r) in self.widgets_plus_rects().filter(|(w, _)| w.visible()) {
             w.draw(&(*offset + r.c1), ctx);
         }
+        ctx.pop_clip();
     }
     fn deselect(&mut self) -> Option<WidgetResponse> {
-        self.widgets_mut().fold(None, |resp, w| { 
-            match resp {
-                Some(r) => { 
-                    match w.deselect() {
-                        Some(ref r2) => Some(combine_response(&r, r2)),
-                        None => Some(r)
-                    }
-                }
-                None => { w.deselect() }
-            }
+        self.widgets_mut().fold(None, |resp, w| {
+            let r = w.deselect();
+            combine_response_opt(resp, r)
         })
     }
-    fn click(&mut self, off_pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        let mut resp = self.click_self(off_pt, ctx).map(|r| (0, r));
-        for (i, (w, r)) in self.widgets_plus_rects_mut().enumerate() {
-            if r.in_bounds(off_pt, &ctx.draw_ctx.viewport) {
-                let child_resp = w.click(&(*off_pt - r.c1), ctx);
-                resp = match resp {
-                    Some((_, ref r)) => {
-                        child_resp.map(|r2| (i, combine_response(r, &r2))).or(resp)
-                    }
-                    None => child_resp.map(|r| (i, r))
-                };
-                break;
-            }
-        }
-        if let Some((i, _)) = resp {
-            for (j, w) in self.widgets_mut().enumerate() {
-                if i != j {
-                    if let Some(wr2) = w.deselect() {
-                        resp = Some((i, combine_response(&resp.unwrap().1, &wr2)));
-                    }
-                }
-            }
-        }
-        self.handle_response(resp)
+    fn scroll(&mut self, off_pt: &Point, delta: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        let i = self.child_at(off_pt, ctx.draw_ctx)?;
+        let off = self.child_offset(i);
+        let resp = self.child_mut(i).and_then(|w| w.scroll(&(*off_pt - off), delta, ctx))?;
+        Some(self.handle_response(i, resp, ctx))
     }
-    fn hover(&mut self, off_pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        let mut resp = self.hover_self(off_pt, ctx).map(|r| (0, r));
-        for (i, (w, rect)) in self.widgets_plus_rects_mut().enumerate() {
-            if rect.in_bounds(off_pt, &ctx.draw_ctx.viewport) {
-                let child_resp = w.hover(&(*off_pt - rect.c1), ctx);
-                resp = match resp {
-                    Some((_, ref r)) => {
-                        child_resp.map(|r2| (i, combine_response(r, &r2))).or(resp)
-                    }
-                    None => child_resp.map(|r| (i, r))
-                };
-                break;
-            }
-        }
-        self.handle_response(resp)
+    fn key_down(&mut self, kc: &Keycode, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        let (i, resp) = self.widgets_mut().enumerate().filter_map(|(i, w)| w.key_down(kc, ctx).map(|r| (i, r))).next()?;
+        Some(self.handle_response(i, resp, ctx))
     }
     fn measure(&self, ctx: &DrawCtx) -> Point {
         self.measure_items(ctx)
@@ -331,6 +426,39 @@ impl<T: WidgetIterT> Widget for T {
     fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
         self.remeasure_items(ctx)
     }
+    fn layout_params(&self) -> Option<&LayoutParams> {
+        self.params()
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        self.widgets_plus_rects().filter_map(|(w, r)| w.baseline(ctx).map(|b| r.c1.y + b)).next()
+    }
+    fn arrange(&mut self, size: &Point, ctx: &DrawCtx) {
+        self.arrange_items(size, ctx)
+    }
+    fn child_at(&self, pt: &Point, ctx: &DrawCtx) -> Option<usize> {
+        self.widgets_plus_rects().position(|(w, r)| w.visible() && r.in_bounds(pt, &ctx.viewport))
+    }
+    fn child_offset(&self, idx: usize) -> Point {
+        self.widgets_plus_rects().nth(idx).map(|(_, r)| r.c1).unwrap_or(Point::origin())
+    }
+    fn child(&self, idx: usize) -> Option<&dyn Widget> {
+        self.widgets_plus_rects().nth(idx).map(|(w, _)| w.as_ref())
+    }
+    fn child_mut(&mut self, idx: usize) -> Option<&mut dyn Widget> {
+        match self.widgets_mut().nth(idx) {
+            Some(w) => Some(w.as_mut()),
+            None => None
+        }
+    }
+    fn mouse_event(&mut self, ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        self.mouse_event_self(ev, pt, ctx)
+    }
+    fn capture(&mut self, ev: &MouseEvent, pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        self.capture_self(ev, pt, ctx)
+    }
+    fn child_response(&mut self, idx: usize, resp: WidgetResponse, ctx: &mut EventCtx) -> WidgetResponse {
+        self.handle_response(idx, resp, ctx)
+    }
     fn serialize(&self, buf: &mut MDDoc) {
         self.serialize_items(buf)
     }
@@ -340,47 +468,104 @@ pub struct WidgetGrid {
     rows: Vec<Vec<Box<dyn Widget>>>,
     widget_rects: Vec<Vec<Rect>>,
     spacing: Point,
-    size: Point
+    size: Point,
+    cell_params: LayoutParams,
+    //size given by the parent through arrange, reset on remeasure
+    min_size: Point
 }
 
 impl WidgetGrid {
     pub fn new(spacing: Point) -> Self {
-        WidgetGrid { rows: Vec::new(), widget_rects: Vec::new(), spacing, size: Point::origin() }
+        WidgetGrid { rows: Vec::new(), widget_rects: Vec::new(), spacing, size: Point::origin(),
+                     cell_params: LayoutParams::default(), min_size: Point::origin() }
+    }
+    //default placement for children that don't have their own LayoutParams
+    pub fn align(mut self, h_align: Align, v_align: Align) -> Self {
+        self.cell_params = self.cell_params.align(h_align, v_align);
+        self
+    }
+    //columns are as wide as their widest cell, cells spanning several columns widen
+    //them evenly if needed and extra width goes to columns with growing cells
+    fn layout(&mut self, ctx: &DrawCtx) {
+        let cells: Vec<Vec<CellInfo>> = self.rows.iter()
+            .map(|row| row.iter().map(|w| CellInfo::new(w, &self.cell_params, ctx)).collect())
+            .collect();
+        //a col_span of 0 still takes a column
+        let n_cols = cells.iter().map(|row| row.iter().map(|c| c.params.col_span.max(1)).sum::<usize>()).max().unwrap_or(0);
+        let spans: Vec<Vec<(usize, usize)>> = cells.iter().map(|row| {
+            let mut col = 0;
+            row.iter().map(|c| {
+                let span = c.params.col_span.min(n_cols - col).max(1);
+                col += span;
+                (col - span, span)
+            }).collect()
+        }).collect();
+        let mut col_widths = vec![0.; n_cols];
+        let mut col_grow = vec![0.; n_cols];
+        for (r, row) in cells.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                let (col, span) = spans[r][c];
+                if span == 1 {
+                    col_widths[col] = cell.size.x.max(col_widths[col]);
+                }
+                col_grow[col] = cell.params.grow.max(col_grow[col]);
+            }
+        }
+        for (r, row) in cells.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                let (col, span) = spans[r][c];
+                let avail = col_widths[col..col + span].iter().sum::<f32>() + self.spacing.x * (span - 1) as f32;
+                if span > 1 && cell.size.x > avail {
+                    let add = (cell.size.x - avail) / span as f32;
+                    col_widths[col..col + span].iter_mut().for_each(|w| *w += add);
+                }
+            }
+        }
+        let natural_width = col_widths.iter().sum::<f32>() + self.spacing.x * (n_cols.max(1) - 1) as f32;
+        let extra = distribute(self.min_size.x - natural_width, &col_grow);
+        col_widths.iter_mut().zip(extra.iter()).for_each(|(w, e)| *w += e);
+        let mut col_offsets = vec![0.; n_cols + 1];
+        for c in 0..n_cols {
+            col_offsets[c + 1] = col_offsets[c] + col_widths[c] + self.spacing.x;
+        }
+        let mut row_offset = 0.;
+        let mut arranged = Vec::new();
+        let mut n_rows = 0;
+        for (r, row) in cells.iter().enumerate() {
+            //rows with every cell hidden are skipped along with their spacing
+            if !row.iter().any(|c| c.visible) {
+                self.widget_rects[r].iter_mut().for_each(|rect| *rect = Rect::new(Point::new(0., row_offset), Point::new(0., row_offset)));
+                continue;
+            }
+            n_rows += 1;
+            let (height, baseline) = row_extent(&row.iter().collect::<Vec<_>>());
+            for (c, cell) in row.iter().enumerate() {
+                let (col, span) = spans[r][c];
+                let cell_rect = Rect::new(Point::new(col_offsets[col], row_offset),
+                    Point::new(col_offsets[col + span] - self.spacing.x, row_offset + height));
+                let rect = place(cell, &cell_rect, baseline);
+                if cell.visible && (rect.width() != cell.size.x || rect.height() != cell.size.y) {
+                    arranged.push((r, c, Point::new(rect.width(), rect.height())));
+                }
+                self.widget_rects[r][c] = rect;
+            }
+            row_offset += height + self.spacing.y;
+        }
+        for (r, c, size) in arranged {
+            self.rows[r][c].arrange(&size, ctx);
+        }
+        let width = col_offsets[n_cols] - if n_cols > 0 { self.spacing.x } else { 0. };
+        let height = row_offset - if n_rows == 0 { 0. } else { self.spacing.y };
+        self.size = Point::new(width.max(self.min_size.x), height.max(self.min_size.y));
     }
 }
 
 impl WidgetIterT for WidgetGrid {
     type Child = Vec<Box<dyn Widget>>;
     fn add(&mut self, new_row: Self::Child, ctx: &DrawCtx) {
-        let max_n_col = self.rows.iter().max_by_key(|r| r.len()).map(|r| r.len()).unwrap_or(0)
-            .max(new_row.len());
-        let mut max_col_widths: Vec<f32> = vec![0.; max_n_col];
-        for row in self.widget_rects.iter() {
-            for (c, rect) in row.iter().enumerate() {
-                max_col_widths[c] = max_col_widths[c].max(rect.width());
-            }
-        }
-        let mut new_rects = Vec::new();
-        let mut max_height: f32 = 0.;
-        for (i, w) in new_row.iter().enumerate() {
-            let m = w.measure(ctx);
-            max_col_widths[i] = max_col_widths[i].max(m.x);
-            let offset = Point::new(0., self.size.y);
-            let new_rect = Rect { c1: offset, c2: offset + m };
-            new_rects.push(new_rect);
-            max_height = max_height.max(m.y);
-        }
-        self.widget_rects.push(new_rects);
-        self.size.y += max_height + self.spacing.y;
-        for row in self.widget_rects.iter_mut() {
-            let mut col_offset = 0.;
-            for (c, rect) in row.iter_mut().enumerate() {
-                rect.set_offset(&Point::new(col_offset, rect.c1.y));
-                col_offset += max_col_widths[c] + self.spacing.x;
-                self.size.x = self.size.x.max(col_offset);
-            }
-        }
+        self.widget_rects.push(vec![Rect::empty(); new_row.len()]);
         self.rows.push(new_row);
+        self.layout(ctx);
     }
     fn widgets_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item=(&'a mut Box<dyn Widget>)> + 'a> {
         Box::new(self.rows.iter_mut().flatten())
@@ -393,10 +578,10 @@ impl WidgetIterT for WidgetGrid {
     }
     fn serialize_items(&self, buf: &mut MDDoc) {
         for r in &self.rows {
-            if r.iter().any(|w| w.do_serialize()) {
+            if r.iter().any(|w| w.do_serialize() && w.visible()) {
                 buf.body.push('*' as u8);
                 buf.body.push(' ' as u8);
-                for w in r  {
+                for w in r.iter().filter(|w| w.visible()) {
                     w.serialize(buf);
                     buf.body.push(' ' as u8);
                 }
@@ -408,31 +593,16 @@ impl WidgetIterT for WidgetGrid {
         self.size
     }
     fn remeasure_items(&mut self, ctx: &DrawCtx) -> Point {
-        if let Some(max_n_col) = self.rows.iter().max_by_key(|r| r.len()).map(|r| r.len()) {
-            let mut max_col_widths: Vec<f32> = vec![0.; max_n_col];
-            let mut row_heights: Vec<f32> = vec![0.; self.rows.len()];
-            for (r, row) in self.rows.iter_mut().enumerate() {
-                for (c, widget) in row.iter_mut().enumerate() {
-                    let m = widget.remeasure(ctx); 
-                    self.widget_rects[r][c] = Rect { c1: Point::origin(), c2: m };
-                    max_col_widths[c] = max_col_widths[c].max(m.x);
-                    row_heights[r] = row_heights[r].max(m.y);
-                }
-            }
-            let mut row_offset = 0.;
-            for (r, row) in self.widget_rects.iter_mut().enumerate() {
-                let mut col_offset = 0.;
-                for (c, rect) in row.iter_mut().enumerate() {
-                    rect.set_offset(&Point::new(col_offset, row_offset));
-                    col_offset += max_col_widths[c] + self.spacing.x;
-                }
-                row_offset += row_heights[r] + self.spacing.y;
-                self.size.x = self.size.x.max(col_offset);
-            }
-            self.size.y = self.size.y.max(row_offset);
-            return self.size
+        for w in self.rows.iter_mut().flatten() {
+            w.remeasure(ctx);
         }
-        Point::origin()
+        self.min_size = Point::origin();
+        self.layout(ctx);
+        self.size
+    }
+    fn arrange_items(&mut self, size: &Point, ctx: &DrawCtx) {
+        self.min_size = *size;
+        self.layout(ctx);
     }
 }
 
@@ -463,18 +633,27 @@ impl Widget for DateWidget {
     fn measure(&self, ctx: &DrawCtx) -> Point {
         self.wl.measure(ctx)
     }
-    fn hover(&mut self, off: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        self.wl.hover(off, ctx)
-    }
-    fn click(&mut self, off: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        self.wl.click(off, ctx)
-    }
     fn deselect(&mut self) -> Option<WidgetResponse> { 
         self.wl.deselect()
     }
     fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
         self.wl.remeasure(ctx)
     }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        self.wl.baseline(ctx)
+    }
+    fn arrange(&mut self, size: &Point, ctx: &DrawCtx) {
+        self.wl.arrange(size, ctx)
+    }
+    fn child_at(&self, _: &Point, _: &DrawCtx) -> Option<usize> {
+        Some(0)
+    }
+    fn child(&self, idx: usize) -> Option<&dyn Widget> {
+        if idx == 0 { Some(&self.wl) } else { None }
+    }
+    fn child_mut(&mut self, idx: usize) -> Option<&mut dyn Widget> {
+        if idx == 0 { Some(&mut self.wl) } else { None }
+    }
     fn serialize(&self, buf: &mut MDDoc) {
         self.wl.get_widget(0).unwrap().serialize(buf);
         buf.body.push('/' as u8);
@@ -489,15 +668,15 @@ impl Widget for DateWidget {
 
 pub struct Label {
     text: String,
-    bg_color: Option<glm::Vec4>,
-    hover_color: Option<glm::Vec4>,
+    bg_color: Option<ThemeColor>,
+    hover_color: Option<ThemeColor>,
     is_hover: bool,
     min_width: Option<f32>,
     text_params: TextParams,
 }
 
 impl Label {
-    pub fn new<T: Into<String>>(text: T, bg_color: Option<glm::Vec4>, hover_color: Option<glm::Vec4>,
+    pub fn new<T: Into<String>>(text: T, bg_color: Option<ThemeColor>, hover_color: Option<ThemeColor>,
                min_width: Option<f32>, text_params: TextParams) -> Self {
         Label { text: text.into(), bg_color, hover_color, min_width, is_hover: false, text_params }
     }
@@ -522,17 +701,15 @@ impl Widget for Label {
             bg_color = self.hover_color;
         }
         if let Some(bg_color) = bg_color {
-            ctx.draw_rect(r.clone(), bg_color, true, Radians(0.));
+            ctx.draw_rect(r.clone(), ctx.theme.color(bg_color), true, Radians(0.));
         }
+        ctx.push_clip(&r);
         let rr = RotateRect::from_rect(r, Radians(0.));
         ctx.render_text.draw(&self.text, &self.text_params, &rr, ctx);
+        ctx.pop_clip();
     }
-    fn hover(&mut self, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
-        if self.hover_color.is_some() && !self.is_hover {
-            self.is_hover = true;
-            Some(just_status(WidgetStatus::REDRAW))
-        }
-        else { Some(just_status(WidgetStatus::FINE)) }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        Some(ctx.render_text.baseline(self.text_params.scale))
     }
     fn deselect(&mut self) -> Option<WidgetResponse> {
         if self.is_hover {
@@ -540,6 +717,18 @@ impl Widget for Label {
             Some(just_status(WidgetStatus::REDRAW))
         } else { None }
     }
+    fn mouse_event(&mut self, ev: &MouseEvent, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+        let is_hover = match *ev {
+            MouseEvent::Enter => true,
+            MouseEvent::Leave => false,
+            _ => return None
+        };
+        if self.hover_color.is_none() || self.is_hover == is_hover {
+            return None
+        }
+        self.is_hover = is_hover;
+        Some(just_status(WidgetStatus::REDRAW))
+    }
     fn serialize(&self, buf: &mut MDDoc) {
         buf.body.extend_from_slice(self.text.as_bytes())
     }
@@ -570,128 +759,329 @@ pub fn new_v_list(widgets: Vec<Box<dyn Widget>>, spacing: u32, ctx: &DrawCtx) ->
     Box::new(wl)
 }
 
-pub struct DropDown {
+//rows shown at once before the list scrolls
+const DROPDOWN_ROWS: usize = 10;
+
+//the list of values shown in the overlay while a DropDown is open.
+//Typing filters it to the values containing the query
+struct DropDownList {
+    values: Vec<String>,
     selected: usize,
-    hover_idx: usize,
-    values_list: WidgetList,
+    query: String,
+    //indices into values that match the query
+    matches: Vec<usize>,
+    //position in matches of the highlighted row and of the first row shown
+    highlight: usize,
+    top: usize,
+    item_size: Point,
     open: bool,
+    popup: PopupId,
 }
 
-impl DropDown {
-    pub fn new<T: Into<String> + AsRef<str>>(values: Vec<T>, selected: usize, ctx: &DrawCtx) -> Self {
-        let mut values_list = WidgetList::new(Orientation::Vertical, 0);
-        let char_size = ctx.render_text.char_size('a', 1.0);
-        let white = rgb_to_f32(255, 255, 255);
-        let lb = rgb_to_f32(168, 238, 240);
-        let mut max_width: f32 = 0.;
-        for v in values.iter() {
-            max_width = max_width.max(ctx.render_text.measure(v.as_ref(), 1.0).x);
+impl DropDownList {
+    fn rows(&self) -> usize {
+        self.matches.len().min(DROPDOWN_ROWS)
+    }
+    fn row_at(&self, pt: &Point) -> Option<usize> {
+        if pt.x < 0. || pt.x > self.item_size.x || pt.y < 0. {
+            return None
+        }
+        let row = (pt.y / self.item_size.y) as usize;
+        if row < self.rows() { Some(self.top + row) } else { None }
+    }
+    //keeps the highlighted value when it still matches, otherwise highlights the first match
+    fn set_query(&mut self, query: String) {
+        let current = self.matches.get(self.highlight).copied();
+        let q = query.to_lowercase();
+        self.matches = (0..self.values.len()).filter(|i| self.values[*i].to_lowercase().contains(&q)).collect();
+        self.query = query;
+        self.highlight = current.and_then(|c| self.matches.iter().position(|m| *m == c)).unwrap_or(0);
+        self.top = 0;
+        self.show_highlight();
+    }
+    fn show_highlight(&mut self) {
+        if self.highlight < self.top {
+            self.top = self.highlight;
+        }
+        else if self.highlight >= self.top + DROPDOWN_ROWS {
+            self.top = self.highlight + 1 - DROPDOWN_ROWS;
+        }
+    }
+    fn move_highlight(&mut self, delta: i32) -> Option<WidgetResponse> {
+        if self.matches.is_empty() {
+            return Some(just_status(WidgetStatus::FINE))
+        }
+        let last = self.matches.len() as i32 - 1;
+        self.highlight = (self.highlight as i32 + delta).max(0).min(last) as usize;
+        self.show_highlight();
+        Some(just_status(WidgetStatus::REDRAW))
+    }
+    fn open(&mut self) {
+        self.open = true;
+        self.matches = (0..self.values.len()).collect();
+        self.query.clear();
+        self.highlight = self.selected;
+        self.top = self.highlight.saturating_sub(DROPDOWN_ROWS / 2).min(self.matches.len().saturating_sub(DROPDOWN_ROWS));
+    }
+    //picks the value in matches at pos and closes the list
+    fn choose(&mut self, pos: usize) -> Option<WidgetResponse> {
+        let resp = WidgetResponse::new(WidgetStatus::REDRAW).msg(Message::ClosePopup(self.popup));
+        let selected = match self.matches.get(pos) {
+            Some(s) => *s,
+            None => return Some(resp)
+        };
+        if selected == self.selected {
+            return Some(resp)
+        }
+        self.selected = selected;
+        Some(resp.msg(Message::Changed))
+    }
+    fn key_down(&mut self, kc: &Keycode) -> Option<WidgetResponse> {
+        match *kc {
+            Keycode::Up => self.move_highlight(-1),
+            Keycode::Down => self.move_highlight(1),
+            Keycode::PageUp => self.move_highlight(-(DROPDOWN_ROWS as i32)),
+            Keycode::PageDown => self.move_highlight(DROPDOWN_ROWS as i32),
+            Keycode::Return => self.choose(self.highlight),
+            Keycode::Escape => Some(WidgetResponse::new(WidgetStatus::REDRAW).msg(Message::ClosePopup(self.popup))),
+            Keycode::Backspace => {
+                let mut query = self.query.clone();
+                query.pop();
+                self.set_query(query);
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            _ => match get_char_from_keycode(*kc) {
+                Some(ch) => {
+                    let query = format!("{}{}", self.query, ch);
+                    self.set_query(query);
+                    Some(just_status(WidgetStatus::REDRAW))
+                }
+                None => Some(just_status(WidgetStatus::FINE))
+            }
+        }
+    }
+}
+
+impl Widget for DropDownList {
+    fn measure(&self, _: &DrawCtx) -> Point {
+        Point::new(self.item_size.x, self.item_size.y * self.rows().max(1) as f32)
+    }
+    fn draw(&self, off: &Point, ctx: &DrawCtx) {
+        let theme = &ctx.theme;
+        let radii = Radii::all(theme.radius);
+        let size = self.measure(ctx);
+        let r = Rect::new(*off, *off + size);
+        let border = Border::new(Point::new(theme.border_width, theme.border_width), theme.palette.focus);
+        ctx.draw_shadow(r.clone(), radii, &theme.shadow(3., 4.));
+        ctx.draw_round_rect(r.clone(), radii, Paint::Solid(theme.palette.widget_bg), Some(&border));
+        if self.matches.is_empty() {
+            let params = TextParams::new().role(ThemeColor::Border);
+            ctx.draw_text("No matches", r, &params);
+            return
+        }
+        let h = self.item_size.y;
+        for (row, pos) in (self.top..self.top + self.rows()).enumerate() {
+            let c1 = *off + Point::new(0., row as f32 * h);
+            let item = Rect::new(c1, c1 + self.item_size);
+            if pos == self.highlight {
+                ctx.draw_rect(item.clone(), theme.palette.hover, true, Radians(0.));
+            }
+            ctx.draw_text(&self.values[self.matches[pos]], item, &TextParams::new());
+        }
+        //thumb along the right edge when some matches are out of view
+        if self.matches.len() > DROPDOWN_ROWS {
+            let (w, n) = (theme.scrollbar_width / 2., self.matches.len() as f32);
+            let y1 = size.y * self.top as f32 / n;
+            let y2 = size.y * (self.top + DROPDOWN_ROWS) as f32 / n;
+            let thumb = Rect::new(*off + Point::new(size.x - w, y1), *off + Point::new(size.x, y2));
+            ctx.draw_round_rect(thumb, Radii::all(w / 2.), Paint::Solid(theme.palette.border), None);
+        }
+    }
+    //presses between rows, or on `No matches`, keep the selection
+    fn mouse_event(&mut self, ev: &MouseEvent, off_pt: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+        match (*ev, self.row_at(off_pt)) {
+            (MouseEvent::Press { .. }, Some(pos)) => self.choose(pos),
+            (MouseEvent::Move, Some(pos)) if pos != self.highlight => {
+                self.highlight = pos;
+                Some(just_status(WidgetStatus::REDRAW))
+            }
+            _ => Some(just_status(WidgetStatus::FINE))
         }
-        max_width += char_size.x;
-        for v in values.into_iter() {
-            values_list.add(Box::new(Label::new(v, Some(white), Some(lb), Some(max_width), TextParams::new())), ctx);
+    }
+    fn scroll(&mut self, _: &Point, delta: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+        let max = self.matches.len().saturating_sub(DROPDOWN_ROWS) as i32;
+        let top = (self.top as i32 + delta.y.signum() as i32).max(0).min(max) as usize;
+        if top == self.top {
+            return Some(just_status(WidgetStatus::FINE))
         }
-        DropDown { values_list, selected, hover_idx: 0, open: false }
+        self.top = top;
+        Some(just_status(WidgetStatus::REDRAW))
+    }
+    fn deselect(&mut self) -> Option<WidgetResponse> {
+        self.open = false;
+        self.query.clear();
+        Some(just_status(WidgetStatus::REDRAW))
     }
-    fn draw_triangle(&self, off: &Point, ctx: &DrawCtx) {
+}
+
+//shows the selected value, the list of values opens in the overlay so it
+//never changes the layout of the widgets around it.
+//With focus the arrow keys, Return and Space open it and typing opens it filtered
+pub struct DropDown {
+    list: Rc<RefCell<DropDownList>>,
+    anchor: Anchor,
+    focused: bool,
+}
+
+impl DropDown {
+    pub fn new<T: Into<String> + AsRef<str>>(values: Vec<T>, selected: usize, ctx: &DrawCtx) -> Self {
+        let values: Vec<String> = values.into_iter().map(|v| v.into()).collect();
+        let list = DropDownList { matches: (0..values.len()).collect(), values, selected, query: String::new(),
+            highlight: 0, top: 0, item_size: Point::origin(), open: false, popup: PopupId::new() };
+        let mut w = DropDown { list: Rc::new(RefCell::new(list)), anchor: new_anchor(), focused: false };
+        w.remeasure(ctx);
+        w
+    }
+    fn draw_background(&self, off: &Point, size: &Point, ctx: &DrawCtx) {
+        let theme = &ctx.theme;
+        let bg = theme.palette.widget_bg;
+        let border_color = if self.list.borrow().open || self.focused { theme.palette.focus } else { theme.palette.border };
+        let border = Border::new(Point::new(theme.border_width, theme.border_width), border_color);
+        ctx.draw_round_rect(Rect::new(*off, *off + *size), Radii::all(theme.radius),
+            Paint::vertical(bg, glm::mix(&bg, &theme.palette.border, 0.3)), Some(&border));
+    }
+    fn draw_triangle(&self, off: &Point, size: &Point, ctx: &DrawCtx) {
         let char_size = ctx.render_text.char_size('a', 1.0);
-        //let height = self.values_list.get_widget(0).as_ref().unwrap().measure(ctx).y;
-        let blue = glm::vec4(0.,0.,1., 1.);
         let tri_center = Point::new(
-            off.x + self.values_list.size.x - char_size.x / 2.,
+            off.x + size.x - char_size.x / 2.,
             off.y + char_size.y / 2.);
-        ctx.draw_iso_tri(tri_center, char_size.x, char_size.y, blue, true, Radians(std::f32::consts::PI));
+        ctx.draw_iso_tri(tri_center, char_size.x, char_size.y, ctx.theme.palette.arrow, true, Radians(std::f32::consts::PI));
+    }
+    fn open(&mut self) -> Option<WidgetResponse> {
+        let mut list = self.list.borrow_mut();
+        list.open();
+        let widget: Rc<RefCell<dyn Widget>> = self.list.clone();
+        let offset = Point::new(0., list.item_size.y + 2.);
+        let popup = Popup::new(list.popup, widget, Rc::clone(&self.anchor), offset);
+        Some(WidgetResponse::new(WidgetStatus::REDRAW).msg(Message::OpenPopup(popup)))
     }
 }
 
 impl Widget for DropDown {
-    fn measure(&self, ctx: &DrawCtx) -> Point {
-        if !self.open {
-            self.values_list.get_widget(0).as_ref().unwrap().measure(ctx)
-        }
-        else {
-            self.values_list.measure(ctx)
-        }
+    fn measure(&self, _: &DrawCtx) -> Point {
+        self.list.borrow().item_size
     }
+    //wide enough for the longest value plus the arrow
     fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
-        if !self.open {
-            self.values_list.get_widget_mut(0).as_mut().unwrap().remeasure(ctx)
-        }
-        else {
-            self.values_list.remeasure(ctx)
-        }
+        let mut list = self.list.borrow_mut();
+        let char_size = ctx.render_text.char_size('a', 1.0);
+        let width = list.values.iter().fold(0f32, |w, v| w.max(ctx.render_text.measure(v, 1.0).x));
+        list.item_size = Point::new(width + char_size.x, ctx.render_text.measure("A", 1.0).y);
+        list.item_size
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        Some(ctx.render_text.baseline(1.0))
     }
+    //shows the query instead of the selection while the list is filtered
     fn draw(&self, off: &Point, ctx: &DrawCtx) {
-        if !self.open {
-            self.values_list.get_widget(self.selected).map(|w| w.draw(off, ctx));
+        self.anchor.set(*off);
+        let size = self.measure(ctx);
+        self.draw_background(off, &size, ctx);
+        let list = self.list.borrow();
+        let r = Rect::new(*off, *off + size);
+        if list.open && !list.query.is_empty() {
+            ctx.draw_text(&list.query, r, &TextParams::new().role(ThemeColor::Focus));
         }
-        else {
-            self.values_list.draw(off, ctx);
+        else if let Some(v) = list.values.get(list.selected) {
+            ctx.draw_text(v, r, &TextParams::new());
         }
-        self.draw_triangle(off, ctx);
+        self.draw_triangle(off, &size, ctx);
     }
-    fn click(&mut self, off_pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        if self.open {
-            self.selected = self.values_list.get_idx(off_pt, ctx.draw_ctx).unwrap_or(0);
-            self.values_list.get_widget_mut(self.hover_idx).and_then(|w| w.deselect());
+    fn mouse_event(&mut self, ev: &MouseEvent, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
+        match *ev {
+            MouseEvent::Press { .. } => (),
+            _ => return None
         }
-        else {
-            self.values_list.get_widget_mut(self.hover_idx).and_then(|w| w.hover(off_pt, ctx));
+        self.focused = true;
+        let (open, popup) = { let list = self.list.borrow(); (list.open, list.popup) };
+        if open {
+            return Some(WidgetResponse::new(WidgetStatus::REDRAW).msg(Message::ClosePopup(popup)))
         }
-        self.open = !self.open;
-        Some(just_status(WidgetStatus::REMEASURE))
+        self.open()
     }
-    fn hover(&mut self, off_pt: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        if self.open {
-            let hover_idx = self.values_list.get_idx(off_pt, ctx.draw_ctx).unwrap_or(0);
-            if hover_idx != self.hover_idx {
-                self.values_list.get_widget_mut(self.hover_idx).map(|w| w.deselect());
-                self.hover_idx = hover_idx;
-            }
-            self.values_list.get_widget_mut(self.hover_idx).and_then(|w| w.hover(off_pt, ctx))
+    fn key_down(&mut self, kc: &Keycode, _: &mut EventCtx) -> Option<WidgetResponse> {
+        if self.list.borrow().open {
+            return self.list.borrow_mut().key_down(kc)
         }
-        else {
-            None
+        if !self.focused {
+            return None
+        }
+        match *kc {
+            Keycode::Up | Keycode::Down | Keycode::Return | Keycode::Space => self.open(),
+            _ => {
+                let ch = get_char_from_keycode(*kc)?;
+                let resp = self.open();
+                self.list.borrow_mut().set_query(ch.to_string());
+                resp
+            }
         }
     }
     fn serialize(&self, buf: &mut MDDoc) {
-        self.values_list.get_widget(self.selected).map(|w| w.serialize(buf));
+        let list = self.list.borrow();
+        list.values.get(list.selected).map(|v| buf.body.extend_from_slice(v.as_bytes()));
     }
     fn deselect(&mut self) -> Option<WidgetResponse> {
-        let r = self.values_list.deselect();
-        if self.open {
-            self.open = false;
-            Some(just_status(WidgetStatus::REMEASURE))
+        let list = self.list.borrow();
+        let was_focused = self.focused;
+        self.focused = false;
+        if list.open {
+            Some(WidgetResponse::new(WidgetStatus::REDRAW).msg(Message::ClosePopup(list.popup)))
         }
-        else { r }
+        else if was_focused { Some(just_status(WidgetStatus::REDRAW)) }
+        else { None }
     }
 }
 
+//colors and padding come from the theme, the label is drawn with the accent text color.
+//onclick runs when the button is pressed and released without the mouse leaving it
 pub struct Button {
-    pub onclick: WidgetResponse,
-    border_rect: BorderRect,
+    pub onclick: Message,
+    size: Point,
+    padding: Point,
     rect: [Rect; 1],
-    label: [Box<dyn Widget>; 1]
+    label: [Box<dyn Widget>; 1],
+    hovered: bool,
+    pressed: bool
 }
 
 impl Button {
-    pub fn new(border: Border, fill_color: glm::Vec4, onclick: WidgetResponse) -> Self 
+    pub fn new(onclick: Message) -> Self 
     {
-        let size = Point::origin();
-        let rect = Rect::empty();
-        let border_rect = BorderRect::new(size, fill_color, border);
-        Button { onclick, border_rect, label: [new_label("")], rect: [rect] }
+        Button { onclick, size: Point::origin(), padding: Point::origin(), label: [new_label("")], rect: [Rect::empty()],
+                 hovered: false, pressed: false }
+    }
+    fn border_rect(&self, ctx: &DrawCtx) -> BorderRect {
+        let theme = &ctx.theme;
+        let fill = theme.palette.accent;
+        let rect = BorderRect::new(self.size, fill, Border::new(self.padding, theme.palette.border))
+            .radius(theme.radius + 1.)
+            .outline(theme.border_width);
+        //pressed buttons lose their shadow and have the gradient flipped so they look pushed in
+        match (self.pressed && self.hovered, self.hovered) {
+            (true, _) => rect.paint(Paint::vertical(fill, lighten(&fill, 0.2))),
+            (false, true) => rect.paint(Paint::vertical(lighten(&fill, 0.6), lighten(&fill, 0.2))).shadow(theme.shadow(2., 3.)),
+            (false, false) => rect.paint(Paint::vertical(lighten(&fill, 0.4), fill)).shadow(theme.shadow(2., 3.))
+        }
     }
 }
 
 impl WidgetIterT for Button {
     type Child = Label; 
-    fn add(&mut self, item: Self::Child, ctx: &DrawCtx) {
-        let size = ctx.render_text.measure(&item.text, item.text_params.scale);
-        self.border_rect.size = size;
+    fn add(&mut self, mut item: Self::Child, ctx: &DrawCtx) {
+        item.text_params.role = ThemeColor::AccentText;
+        self.size = ctx.render_text.measure(&item.text, item.text_params.scale);
         self.label[0] = Box::new(item);
-        let off = self.border_rect.border.width;
-        self.rect[0] = Rect { c1: off, c2: off + size };
+        self.remeasure_items(ctx);
     }
     fn widgets_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item=(&'a mut Box<dyn Widget>)> + 'a> {
         Box::new(self.label.iter_mut())
@@ -703,21 +1093,40 @@ impl WidgetIterT for Button {
         Box::new(self.label.iter_mut().zip(self.rect.iter_mut()))
     }
     fn measure_items(&self, _: &DrawCtx) -> Point {
-       self.border_rect.size + self.border_rect.border.width * Point::new(2., 2.)
-    }
-    fn hover_self(&mut self, _: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        *ctx.cursor = SystemCursor::Hand;
-        Some(just_status(WidgetStatus::FINE))
+       self.size + self.padding * Point::new(2., 2.)
     }
     fn draw_self(&self, offset: &Point, ctx: &DrawCtx) {
-        self.border_rect.draw(*offset, ctx);
+        self.border_rect(ctx).draw(*offset, ctx);
     }
-    fn click_self(&mut self, _: &Point, _: &mut EventCtx) -> Option<WidgetResponse> {
-        Some((self.onclick.0, Rc::clone(&self.onclick.1)))
+    fn mouse_event_self(&mut self, ev: &MouseEvent, _: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        match *ev {
+            MouseEvent::Move => {
+                *ctx.cursor = SystemCursor::Hand;
+                return Some(just_status(WidgetStatus::FINE))
+            }
+            MouseEvent::Enter => self.hovered = true,
+            MouseEvent::Leave => self.hovered = false,
+            MouseEvent::Press { .. } => self.pressed = true,
+            MouseEvent::Release => self.pressed = false,
+            MouseEvent::Click { .. } => return Some(just_msg(self.onclick.clone())),
+            _ => return None
+        }
+        Some(just_status(WidgetStatus::REDRAW))
     }
     fn remeasure_items(&mut self, ctx: &DrawCtx) -> Point {
+        self.size = self.label[0].remeasure(ctx);
+        self.padding = ctx.theme.padding;
+        self.rect[0] = Rect { c1: self.padding, c2: self.padding + self.size };
         self.measure_items(ctx)
     }
+    //grows the button and keeps the label centered in it
+    fn arrange_items(&mut self, size: &Point, ctx: &DrawCtx) {
+        let label_size = self.label[0].measure(ctx);
+        let inner = *size - self.padding * Point::new(2., 2.);
+        self.size = Point::new(inner.x.max(label_size.x), inner.y.max(label_size.y));
+        let c1 = self.padding + (self.size - label_size) / 2.;
+        self.rect[0] = Rect { c1, c2: c1 + label_size };
+    }
     fn serialize_items(&self, _: &mut MDDoc) { }
 }
 
@@ -764,37 +1173,191 @@ impl SerializeT for SkipSerializer {
 }
 
 pub struct SerializeWidget<T: SerializeT> {
-    serializer: std::marker::PhantomData<T>,
-    widget: Box<dyn Widget>
+    serializer: std::marker::PhantomData<T>
 }
 
 pub fn new_serialize<T: SerializeT + 'static>(widget: Box<dyn Widget>) -> Box<dyn Widget> {
-    Box::new(SerializeWidget::<T>{ serializer: std::marker::PhantomData::<T>, widget })
+    Box::new(Wrapper::new(SerializeWidget::<T>{ serializer: std::marker::PhantomData::<T> }, widget))
 }
 
-impl<T: SerializeT> Widget for SerializeWidget<T> {
-    fn measure(&self, ctx: &DrawCtx) -> Point {
-        self.widget.measure(ctx)
+impl<T: SerializeT> WrapperT for SerializeWidget<T> {
+    fn do_serialize(&self, _: &Box<dyn Widget>) -> bool {
+        T::do_serialize()
     }
-    fn draw(&self, offset: &Point, ctx: &DrawCtx) {
-        self.widget.draw(offset, ctx);
+    fn serialize(&self, w: &Box<dyn Widget>, buf: &mut MDDoc) {
+        T::serialize(w, buf);
+    }
+}
+
+//what a wrapper adds to the one widget it wraps, see Wrapper
+pub trait WrapperT {
+    //while false the widget takes no space, ignores input and isn't serialized
+    fn shown(&self) -> bool { true }
+    fn tooltip(&self) -> Option<&str> { None }
+    fn publish(&self, _: &Box<dyn Widget>) { }
+    fn do_serialize(&self, w: &Box<dyn Widget>) -> bool { w.do_serialize() }
+    fn serialize(&self, w: &Box<dyn Widget>, buf: &mut MDDoc) { w.serialize(buf) }
+}
+
+//passes everything through to its widget, which is child 0, apart from what T adds
+pub struct Wrapper<T: WrapperT> {
+    wrap: T,
+    widget: Box<dyn Widget>
+}
+
+impl<T: WrapperT> Wrapper<T> {
+    pub fn new(wrap: T, widget: Box<dyn Widget>) -> Self {
+        Wrapper { wrap, widget }
     }
-    fn click(&mut self, off: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        self.widget.click(off, ctx)
+}
+
+impl<T: WrapperT> Widget for Wrapper<T> {
+    fn measure(&self, ctx: &DrawCtx) -> Point {
+        if self.wrap.shown() { self.widget.measure(ctx) } else { Point::origin() }
     }
-    fn hover(&mut self, off: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
-        self.widget.hover(off, ctx)
+    fn draw(&self, offset: &Point, ctx: &DrawCtx) {
+        if self.wrap.shown() {
+            self.widget.draw(offset, ctx);
+        }
     }
+    //measures the widget even while hidden so it is ready to show
     fn remeasure(&mut self, ctx: &DrawCtx) -> Point {
-        self.widget.remeasure(ctx)
+        let size = self.widget.remeasure(ctx);
+        if self.wrap.shown() { size } else { Point::origin() }
     }
     fn deselect(&mut self) -> Option<WidgetResponse> {
         self.widget.deselect()
     }
+    fn scroll(&mut self, off: &Point, delta: &Point, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        if !self.wrap.shown() {
+            return None
+        }
+        self.widget.scroll(off, delta, ctx)
+    }
+    fn key_down(&mut self, kc: &Keycode, ctx: &mut EventCtx) -> Option<WidgetResponse> {
+        if !self.wrap.shown() {
+            return None
+        }
+        self.widget.key_down(kc, ctx)
+    }
+    fn layout_params(&self) -> Option<&LayoutParams> {
+        self.widget.layout_params()
+    }
+    fn baseline(&self, ctx: &DrawCtx) -> Option<f32> {
+        self.widget.baseline(ctx)
+    }
+    fn arrange(&mut self, size: &Point, ctx: &DrawCtx) {
+        if self.wrap.shown() {
+            self.widget.arrange(size, ctx)
+        }
+    }
+    fn child_at(&self, _: &Point, _: &DrawCtx) -> Option<usize> {
+        if self.wrap.shown() { Some(0) } else { None }
+    }
+    fn child(&self, idx: usize) -> Option<&dyn Widget> {
+        if idx == 0 { Some(self.widget.as_ref()) } else { None }
+    }
+    fn child_mut(&mut self, idx: usize) -> Option<&mut dyn Widget> {
+        if idx == 0 { Some(self.widget.as_mut()) } else { None }
+    }
+    fn publish(&self) {
+        self.wrap.publish(&self.widget)
+    }
+    fn tooltip(&self) -> Option<&str> {
+        self.wrap.tooltip()
+    }
+    fn visible(&self) -> bool {
+        self.wrap.shown() && self.widget.visible()
+    }
     fn do_serialize(&self) -> bool {
-        T::do_serialize()
+        self.wrap.shown() && self.wrap.do_serialize(&self.widget)
     }
     fn serialize(&self, buf: &mut MDDoc) {
-        T::serialize(&self.widget, buf);
+        if self.wrap.shown() {
+            self.wrap.serialize(&self.widget, buf)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::form::{FormValues, new_field, show_when};
+
+    //serializes its text, enough to stand in for a form widget
+    struct Text(&'static str);
+
+    impl Widget for Text {
+        fn draw(&self, _: &Point, _: &DrawCtx) { }
+        fn serialize(&self, buf: &mut MDDoc) {
+            buf.body.extend_from_slice(self.0.as_bytes())
+        }
+    }
+
+    fn text(s: &'static str) -> Box<dyn Widget> {
+        Box::new(Text(s))
+    }
+
+    fn list(values: &[&str], selected: usize) -> DropDownList {
+        DropDownList { matches: (0..values.len()).collect(), values: values.iter().map(|v| v.to_string()).collect(), selected,
+            query: String::new(), highlight: selected, top: 0, item_size: Point::new(10., 10.), open: true, popup: PopupId::new() }
+    }
+
+    #[test]
+    fn query_filters_ignoring_case() {
+        let mut l = list(&["Trend", "Mean Reversion", "LEVEL_E Extension", "Range"], 0);
+        l.set_query(String::from("E"));
+        assert_eq!(l.matches, vec![0, 1, 2, 3]);
+        l.set_query(String::from("ext"));
+        assert_eq!(l.matches, vec![2]);
+        assert_eq!(l.highlight, 0);
+        l.set_query(String::from("nothing"));
+        assert!(l.matches.is_empty());
+    }
+
+    #[test]
+    fn query_keeps_the_highlighted_value() {
+        let mut l = list(&["Trend", "Mean Reversion", "Range"], 2);
+        l.set_query(String::from("r"));
+        assert_eq!(l.matches, vec![0, 1, 2]);
+        assert_eq!(l.matches[l.highlight], 2);
+        l.set_query(String::from("re"));
+        assert_eq!(l.matches, vec![0, 1]);
+        assert_eq!(l.highlight, 0);
+    }
+
+    #[test]
+    fn query_scrolls_to_the_highlight() {
+        let values: Vec<String> = (0..DROPDOWN_ROWS * 2).map(|i| format!("v{}", i)).collect();
+        let refs: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+        let last = refs.len() - 1;
+        let mut l = list(&refs, last);
+        l.set_query(String::from("V"));
+        assert_eq!(l.highlight, last);
+        assert_eq!(l.top, last + 1 - DROPDOWN_ROWS);
+        assert_eq!(l.rows(), DROPDOWN_ROWS);
+    }
+
+    fn serialized(grid: &WidgetGrid) -> String {
+        let mut buf = MDDoc::empty();
+        grid.serialize_items(&mut buf);
+        String::from_utf8(buf.body).unwrap()
+    }
+
+    #[test]
+    fn hidden_rows_are_not_serialized() {
+        let values = FormValues::new();
+        let mut grid = WidgetGrid::new(Point::origin());
+        grid.rows = vec![
+            vec![text("Strategy:"), new_field("strategy", text("LEVEL_D"), &values)],
+            show_when(&values, |v| v.get("strategy").map_or(false, |s| s.starts_with("LEVEL_")), vec![text("Level:"), text("LEVEL_A")]),
+            show_when(&values, |v| v.get("strategy").map_or(false, |s| s == "Trend"), vec![text("Trend:"), text("Up")])
+        ];
+        grid.widget_rects = grid.rows.iter().map(|r| vec![Rect::empty(); r.len()]).collect();
+        //nothing published yet, so no rule holds
+        assert_eq!(serialized(&grid), "* Strategy: LEVEL_D \n");
+        walk(&grid, &mut |w| w.publish());
+        assert_eq!(values.get("strategy"), Some(String::from("LEVEL_D")));
+        assert_eq!(serialized(&grid), "* Strategy: LEVEL_D \n* Level: LEVEL_A \n");
     }
 }